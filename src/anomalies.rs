@@ -0,0 +1,180 @@
+#![warn(clippy::all)]
+
+//! Scoring of how unusual the parent selection of each transaction is, to point at
+//! misbehaving issuers in a dump.
+
+use crate::graph::Graph;
+use crate::id::{Id, NonRootId};
+use crate::stats::span_to_f64;
+use crate::transaction::Transaction;
+use conv::{errors::PosOverflow, ValueFrom};
+use derive_more::Display;
+use std::collections::HashSet as Set;
+
+/// How unusual the parent selection of a transaction is, displayed as a tab-separated row.
+/// Each feature is given as a z-score, the number of standard deviations between the value
+/// of the transaction and the average value.
+#[derive(PartialEq, Debug, Display)]
+#[display(
+    fmt = "{}\t{:.2}\t{:.2}\t{:.2}\t{:.2}",
+    id,
+    score,
+    staleness,
+    late_root,
+    same_parents
+)]
+pub struct Anomaly {
+    /// The ID of the transaction.
+    pub id: usize,
+
+    /// The sum of the positive z-scores, so that features that are lower than usual do
+    /// not make up for the ones that are higher.
+    pub score: f64,
+
+    /// How far the oldest parent is behind the average timestamp of the tips when the
+    /// transaction arrives.
+    pub staleness: f64,
+
+    /// The number of references to the Root, weighted by how late in the trace the
+    /// transaction arrives.
+    pub late_root: f64,
+
+    /// Whether both references are the same transaction. References to the Root are
+    /// already accounted for by `late_root`.
+    pub same_parents: f64,
+}
+
+impl Graph {
+    /// Score every transaction on how unusual its parent selection is, from the most
+    /// unusual to the least. Transactions are replayed by timestamp to know the tips,
+    /// the transactions nothing refers to yet, when each of them arrives.
+    pub fn anomalies(&self) -> Result<Vec<Anomaly>, PosOverflow<usize>> {
+        let mut order: Vec<&Transaction> = self.transactions().collect();
+        order.sort_by_key(|t| (t.timestamp(), usize::from(t.id())));
+
+        let mut tips = Set::new();
+        let mut referenced = Set::new();
+        let mut sum_of_tip_timestamps = 0.0;
+        let last = f64::value_from(order.len().saturating_sub(1).max(1))?;
+        let mut features = Vec::with_capacity(order.len());
+
+        for (i, t) in order.iter().enumerate() {
+            let parents: Vec<NonRootId> = [t.left(), t.right()]
+                .iter()
+                .filter_map(|&id| match id {
+                    Id::Transaction(id) => Some(id),
+                    Id::Root => None,
+                })
+                .collect();
+            let n_root_references = f64::value_from(2 - parents.len())?;
+
+            let oldest = parents.iter().map(|&p| self[p].timestamp()).min();
+            let staleness = match oldest {
+                Some(oldest) if !tips.is_empty() => {
+                    let average = sum_of_tip_timestamps / f64::value_from(tips.len())?;
+                    (average - span_to_f64(oldest)?).max(0.0)
+                }
+                _ => 0.0,
+            };
+            let late_root = n_root_references * f64::value_from(i)? / last;
+            let same_parents = match (t.left(), t.right()) {
+                (Id::Transaction(left), Id::Transaction(right)) if left == right => 1.0,
+                _ => 0.0,
+            };
+            features.push([staleness, late_root, same_parents]);
+
+            // The transaction becomes a tip and its parents stop being tips.
+            for parent in parents {
+                if referenced.insert(parent) && tips.remove(&parent) {
+                    sum_of_tip_timestamps -= span_to_f64(self[parent].timestamp())?;
+                }
+            }
+            if !referenced.contains(&t.id()) {
+                tips.insert(t.id());
+                sum_of_tip_timestamps += span_to_f64(t.timestamp())?;
+            }
+        }
+
+        let z_scores = z_scores(&features)?;
+        let mut anomalies: Vec<Anomaly> = order
+            .iter()
+            .zip(z_scores)
+            .map(|(t, [staleness, late_root, same_parents])| Anomaly {
+                id: t.id().into(),
+                score: [staleness, late_root, same_parents]
+                    .iter()
+                    .map(|z| z.max(0.0))
+                    .sum(),
+                staleness,
+                late_root,
+                same_parents,
+            })
+            .collect();
+
+        anomalies.sort_by(|a, b| b.score.total_cmp(&a.score).then(a.id.cmp(&b.id)));
+        Ok(anomalies)
+    }
+}
+
+/// Standardize each feature, giving 0 to every value of a feature that does not vary.
+fn z_scores(features: &[[f64; 3]]) -> Result<Vec<[f64; 3]>, PosOverflow<usize>> {
+    let n = f64::value_from(features.len())?;
+    let mut means = [0.0; 3];
+    let mut deviations = [0.0; 3];
+
+    for f in 0..3 {
+        means[f] = features.iter().map(|values| values[f]).sum::<f64>() / n;
+        let variance = features
+            .iter()
+            .map(|values| (values[f] - means[f]).powi(2))
+            .sum::<f64>()
+            / n;
+        deviations[f] = variance.sqrt();
+    }
+
+    Ok(features
+        .iter()
+        .map(|values| {
+            let mut z = [0.0; 3];
+            for f in 0..3 {
+                if deviations[f] > 0.0 {
+                    z[f] = (values[f] - means[f]) / deviations[f];
+                }
+            }
+            z
+        })
+        .collect())
+}
+
+#[cfg(test)]
+mod anomalies_tests {
+    use crate::graph::Graph;
+    use std::convert::TryFrom;
+    use std::io::BufReader;
+
+    fn anomalies(input: &str) -> Vec<String> {
+        let graph = Graph::try_from(BufReader::new(input.as_bytes())).unwrap();
+        let anomalies = graph.anomalies().unwrap();
+        anomalies.iter().map(ToString::to_string).collect()
+    }
+
+    #[test]
+    fn suspects() {
+        // Transaction 7 refers twice to the first transaction long after it was approved,
+        // and transaction 8 only refers to the Root at the end of the trace.
+        let input = "7\n1 1 0\n2 1 1\n3 2 2\n4 3 3\n5 4 4\n2 2 5\n1 1 6\n";
+        let anomalies = anomalies(input);
+        assert_eq!(anomalies.len(), 7);
+        assert_eq!(anomalies[0], "7\t4.74\t2.29\t-0.45\t2.45");
+        assert_eq!(anomalies[1], "8\t2.44\t-0.76\t2.44\t-0.41");
+    }
+
+    #[test]
+    fn uniform() {
+        // Without variations, nothing stands out. Both references of the first
+        // transaction point to the Root, which does not count as the same parents.
+        let anomalies = anomalies("1\n1 1 0\n");
+        assert_eq!(anomalies, vec!["2\t0.00\t0.00\t0.00\t0.00"]);
+        assert_eq!(super::z_scores(&[]).unwrap(), Vec::<[f64; 3]>::new());
+    }
+}
@@ -0,0 +1,316 @@
+#![warn(clippy::all)]
+
+//! Statistics about how transactions arrive over time, to tell whether arrivals are as
+//! random as a Poisson process or come in bursts.
+
+use crate::graph::Graph;
+use crate::stats::{span_to_f64, Distribution, Stat, StatResult};
+use crate::time::{Duration, TimeUnit, Timestamp};
+use crate::transaction::Transaction;
+use conv::{errors::PosOverflow, ValueFrom};
+use std::collections::BTreeMap;
+use std::convert::TryFrom;
+use std::fmt::{self, Display};
+
+/// The smallest number of expected observations in a bin of the goodness-of-fit test.
+const MIN_EXPECTED: f64 = 5.0;
+
+/// The result of a chi-square test of the number of transactions per unit of time against
+/// a Poisson distribution with the same mean.
+#[derive(PartialEq, Debug, Clone, Copy)]
+pub struct PoissonFit {
+    pub chi_square: f64,
+
+    /// The degrees of freedom, the number of bins minus 2 since the mean is estimated.
+    pub degrees_of_freedom: usize,
+
+    /// The probability of a worse fit if the arrivals were a Poisson process, NaN without
+    /// degrees of freedom.
+    pub p_value: f64,
+}
+
+impl PoissonFit {
+    /// Compare the number of units of time in which each number of transactions arrived,
+    /// indexed by the number of transactions, with the numbers expected from a Poisson
+    /// distribution.
+    fn new(observed: &[f64]) -> Self {
+        let slots: f64 = observed.iter().sum();
+        let n_transactions: f64 = observed
+            .iter()
+            .zip(0..)
+            .map(|(o, k)| o * f64::from(k))
+            .sum();
+        let lambda = n_transactions / slots;
+
+        // Bins of consecutive numbers of transactions are merged until enough observations
+        // are expected in each of them, and the last bin includes the tail.
+        let mut bins = Vec::new();
+        let (mut bin_observed, mut bin_expected) = (0.0, 0.0);
+        let (mut ln_probability, mut cumulative) = (-lambda, 0.0);
+
+        for (k, o) in observed.iter().enumerate() {
+            if k > 0 {
+                ln_probability += lambda.ln() - (k as f64).ln();
+            }
+            let probability = ln_probability.exp();
+            cumulative += probability;
+            bin_observed += o;
+            bin_expected += slots * probability;
+
+            if bin_expected >= MIN_EXPECTED {
+                bins.push((bin_observed, bin_expected));
+                bin_observed = 0.0;
+                bin_expected = 0.0;
+            }
+        }
+
+        bin_expected += slots * (1.0 - cumulative).max(0.0);
+        match bins.last_mut() {
+            Some(last) if bin_expected < MIN_EXPECTED => {
+                last.0 += bin_observed;
+                last.1 += bin_expected;
+            }
+            _ => bins.push((bin_observed, bin_expected)),
+        }
+
+        let chi_square = bins.iter().map(|(o, e)| (o - e) * (o - e) / e).sum();
+        let degrees_of_freedom = bins.len().saturating_sub(2);
+        Self {
+            chi_square,
+            degrees_of_freedom,
+            p_value: chi_square_survival(chi_square, degrees_of_freedom as f64),
+        }
+    }
+}
+
+impl Display for PoissonFit {
+    fn fmt(&self, f: &mut fmt::Formatter) -> fmt::Result {
+        write!(
+            f,
+            "CHI-SQUARE {:.2}, DF {}, P-VALUE {:.2}",
+            self.chi_square, self.degrees_of_freedom, self.p_value
+        )
+    }
+}
+
+/// The probability that a chi-square variable with `k` degrees of freedom exceeds `x`,
+/// using the Wilson-Hilferty approximation.
+fn chi_square_survival(x: f64, k: f64) -> f64 {
+    if k < 1.0 {
+        return f64::NAN;
+    }
+
+    let variance = 2.0 / (9.0 * k);
+    let z = ((x / k).cbrt() - (1.0 - variance)) / variance.sqrt();
+    erfc(z / std::f64::consts::SQRT_2) / 2.0
+}
+
+/// The complementary error function, within 1.5e-7 (Abramowitz and Stegun, 7.1.26).
+fn erfc(x: f64) -> f64 {
+    let t = 1.0 / (1.0 + 0.327_591_1 * x.abs());
+    let polynomial = t
+        * (0.254_829_592
+            + t * (-0.284_496_736
+                + t * (1.421_413_741 + t * (-1.453_152_027 + t * 1.061_405_429))));
+    let erfc = polynomial * (-x * x).exp();
+
+    if x < 0.0 {
+        2.0 - erfc
+    } else {
+        erfc
+    }
+}
+
+/// The result of the arrival statistics.
+pub struct ArrivalsResult {
+    pub unit: TimeUnit,
+
+    /// The gaps between the timestamps of consecutive transactions, 0 for transactions
+    /// sharing a timestamp.
+    pub gaps: Distribution,
+
+    /// The numbers of transactions sharing each timestamp.
+    pub txs_per_timestamp: Distribution,
+
+    /// The burstiness coefficient of the gaps, (σ - μ) / (σ + μ): -1 for perfectly regular
+    /// arrivals, about 0 for a Poisson process and close to 1 for bursts.
+    pub burstiness: f64,
+
+    pub poisson_fit: PoissonFit,
+}
+
+impl ArrivalsResult {
+    fn gap(&self, gap: Option<u64>) -> f64 {
+        // All the gaps were converted when the result was computed, so they fit.
+        gap.map_or(f64::NAN, |gap| span_to_f64(gap).unwrap_or(f64::NAN))
+    }
+}
+
+impl Display for ArrivalsResult {
+    fn fmt(&self, f: &mut fmt::Formatter) -> fmt::Result {
+        let [min, median, p90, max] = self.gaps.summary().map(|gap| Duration {
+            length: self.gap(gap),
+            unit: self.unit,
+        });
+        writeln!(
+            f,
+            "> INTER-ARRIVAL GAPS: MIN {}, MEDIAN {}, P90 {}, MAX {}",
+            min, median, p90, max
+        )?;
+
+        writeln!(f, "> TXS PER TIMESTAMP: {}", self.txs_per_timestamp)?;
+
+        writeln!(f, "> BURSTINESS: {:.2}", self.burstiness)?;
+        write!(f, "> POISSON FIT: {}", self.poisson_fit)
+    }
+}
+
+impl StatResult for ArrivalsResult {
+    fn values(&self) -> Vec<(&'static str, f64)> {
+        let [_, median_gap, p90_gap, max_gap] = self.gaps.summary().map(|g| self.gap(g));
+        let [_, median_txs, _, max_txs] = self
+            .txs_per_timestamp
+            .summary()
+            .map(|count| count.map_or(f64::NAN, |count| span_to_f64(count).unwrap_or(f64::NAN)));
+        vec![
+            ("MEDIAN INTER-ARRIVAL GAP", median_gap),
+            ("P90 INTER-ARRIVAL GAP", p90_gap),
+            ("MAX INTER-ARRIVAL GAP", max_gap),
+            ("MEDIAN TXS PER TIMESTAMP", median_txs),
+            ("MAX TXS PER TIMESTAMP", max_txs),
+            ("BURSTINESS", self.burstiness),
+            ("POISSON CHI-SQUARE", self.poisson_fit.chi_square),
+            ("POISSON P-VALUE", self.poisson_fit.p_value),
+        ]
+    }
+}
+
+/// The accumulator for the number of transactions arriving at each timestamp.
+#[derive(Default)]
+pub struct Arrivals {
+    /// The unit of the timestamps. When it is not known beforehand, it is taken from the
+    /// metadata of the graph the first time a transaction is observed.
+    pub(crate) unit: Option<TimeUnit>,
+
+    /// The number of transactions with each timestamp.
+    counts: BTreeMap<Timestamp, usize>,
+}
+
+impl Arrivals {
+    /// The arrivals in the time unit of the graph.
+    pub fn new(graph: &Graph) -> Self {
+        Self::with_unit(TimeUnit::of(graph))
+    }
+
+    pub fn with_unit(unit: TimeUnit) -> Self {
+        Self {
+            unit: Some(unit),
+            counts: BTreeMap::new(),
+        }
+    }
+}
+
+impl Stat<'_> for Arrivals {
+    fn accumulate(&mut self, transaction: &Transaction) {
+        *self.counts.entry(transaction.timestamp()).or_insert(0) += 1;
+    }
+
+    fn result(&self, _n_transactions: f64) -> Result<Box<dyn StatResult>, PosOverflow<usize>> {
+        let mut gaps = Distribution::default();
+        let mut txs_per_timestamp = Distribution::default();
+        let mut previous = None;
+
+        for (&timestamp, &count) in &self.counts {
+            if let Some(previous) = previous {
+                gaps.add(timestamp - previous, 1);
+            }
+            gaps.add(0, count - 1);
+            txs_per_timestamp.add(u64::try_from(count).expect("Counts fit in 64 bits"), 1);
+            previous = Some(timestamp);
+        }
+
+        let (mean, deviation) = gaps.mean_and_deviation()?;
+
+        // The number of units of time in the span in which each number of transactions
+        // arrived, including the units in which none did.
+        let mut observed = Vec::new();
+        for (count, n_timestamps) in txs_per_timestamp.iter() {
+            let count = usize::try_from(count).expect("Counts come from usizes");
+            if observed.len() <= count {
+                observed.resize(count + 1, 0.0);
+            }
+            observed[count] = f64::value_from(n_timestamps)?;
+        }
+        if let (Some((first, _)), Some((last, _))) =
+            (self.counts.iter().next(), self.counts.iter().next_back())
+        {
+            let n_units = span_to_f64(last - first)? + 1.0;
+            observed[0] = n_units - f64::value_from(self.counts.len())?;
+        }
+
+        Ok(Box::new(ArrivalsResult {
+            unit: self.unit.unwrap_or_default(),
+            gaps,
+            txs_per_timestamp,
+            burstiness: (deviation - mean) / (deviation + mean),
+            poisson_fit: PoissonFit::new(&observed),
+        }))
+    }
+}
+
+#[cfg(test)]
+mod arrivals_tests {
+    use super::Arrivals;
+    use crate::graph::Graph;
+    use crate::stats::{self, Stat};
+    use std::convert::TryFrom;
+    use std::io::BufReader;
+
+    fn arrivals(input: &str) -> String {
+        let graph = Graph::try_from(BufReader::new(input.as_bytes())).unwrap();
+        let stats: Vec<Box<dyn Stat>> = vec![Box::new(Arrivals::new(&graph))];
+        let results = stats::accumulate(&graph, stats).unwrap();
+        results[0].to_string()
+    }
+
+    #[test]
+    fn bursts() {
+        let input = "7\n1 1 10\n1 1 10\n2 3 11\n4 4 13\n5 5 13\n6 6 13\n7 8 20\n";
+        assert_eq!(
+            arrivals(input),
+            "> INTER-ARRIVAL GAPS: MIN 0.00 ticks, MEDIAN 0.00 ticks, P90 7.00 ticks, \
+             MAX 7.00 ticks\n\
+             > TXS PER TIMESTAMP: MIN 1, MEDIAN 1, P90 3, MAX 3\n\
+             > BURSTINESS: 0.20\n\
+             > POISSON FIT: CHI-SQUARE 0.51, DF 0, P-VALUE NaN"
+        );
+    }
+
+    #[test]
+    fn regular() {
+        let mut input = String::from("time-unit: s\n100\n");
+        for i in 0..100 {
+            input.push_str(&format!("{} 1 {}\n", i + 1, i));
+        }
+
+        assert_eq!(
+            arrivals(&input),
+            "> INTER-ARRIVAL GAPS: MIN 1.00 s, MEDIAN 1.00 s, P90 1.00 s, MAX 1.00 s\n\
+             > TXS PER TIMESTAMP: MIN 1, MEDIAN 1, P90 1, MAX 1\n\
+             > BURSTINESS: -1.00\n\
+             > POISSON FIT: CHI-SQUARE 171.83, DF 1, P-VALUE 0.00"
+        );
+    }
+
+    #[test]
+    fn empty() {
+        assert_eq!(
+            arrivals("0\n"),
+            "> INTER-ARRIVAL GAPS: MIN NaN ticks, MEDIAN NaN ticks, P90 NaN ticks, \
+             MAX NaN ticks\n\
+             > TXS PER TIMESTAMP: MIN NaN, MEDIAN NaN, P90 NaN, MAX NaN\n\
+             > BURSTINESS: NaN\n\
+             > POISSON FIT: CHI-SQUARE NaN, DF 0, P-VALUE NaN"
+        );
+    }
+}
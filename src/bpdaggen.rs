@@ -1,5 +1,6 @@
 use log::info;
-use rand::Rng;
+use rand::rngs::StdRng;
+use rand::{Rng, SeedableRng};
 use structopt::StructOpt;
 
 #[derive(StructOpt)]
@@ -7,6 +8,9 @@ use structopt::StructOpt;
 struct Opt {
     #[structopt(name = "n_vertices", help = "Number of vertices")]
     vertices: usize,
+
+    #[structopt(long = "seed", help = "Seed of the random number generator")]
+    seed: Option<u64>,
 }
 
 fn main() {
@@ -14,7 +18,10 @@ fn main() {
 
     let opts = Opt::from_args();
 
-    let mut rng = rand::thread_rng();
+    // Always use a known seed so that it can be recorded in the output and the graph can
+    // be generated again.
+    let seed = opts.seed.unwrap_or_else(|| rand::thread_rng().gen());
+    let mut rng = StdRng::seed_from_u64(seed);
 
     let mut nodes: Vec<(usize, usize, usize)> = Vec::with_capacity(opts.vertices);
     let mut reds: Vec<usize> = Vec::with_capacity(opts.vertices / 2);
@@ -70,6 +77,8 @@ fn main() {
         }
     }
 
+    println!("generator: bpdaggen");
+    println!("seed: {}", seed);
     println!("{}", reds.len() + blues.len() - 1); // Print the number of nodes in the file
     for vertex in nodes.iter().skip(1) {
         println!("{} {} {}", vertex.0, vertex.1, vertex.2);
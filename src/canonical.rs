@@ -0,0 +1,389 @@
+#![warn(clippy::all)]
+
+//! Canonical relabeling and hashing of graphs, independent of how transactions were
+//! numbered by the exporter of a dump.
+
+use crate::graph::Graph;
+use crate::id::{Id, NonRootId};
+use crate::time::Timestamp;
+use crate::transaction::Transaction;
+use std::cmp::Reverse;
+use std::collections::{BinaryHeap, HashMap as Map, HashSet as Set};
+use std::convert::TryFrom;
+
+/// The 64-bit FNV-1a hash function, used for hashes that must not change across platforms
+/// or releases.
+struct Fnv1a(u64);
+
+impl Fnv1a {
+    fn new() -> Self {
+        Self(0xcbf2_9ce4_8422_2325)
+    }
+
+    fn write(&mut self, value: u64) {
+        for byte in value.to_le_bytes().iter() {
+            self.0 ^= u64::from(*byte);
+            self.0 = self.0.wrapping_mul(0x0000_0100_0000_01b3);
+        }
+    }
+
+    fn finish(&self) -> u64 {
+        self.0
+    }
+}
+
+/// A color per transaction, shared by the transactions that refinement cannot tell apart.
+type Colors = Map<NonRootId, u64>;
+
+/// The order in which transactions are numbered: by timestamp, by the new IDs of their
+/// references and finally by their color.
+type Key = (Timestamp, usize, usize, u64);
+
+/// A relabeled transaction: the new IDs of its references and its timestamp.
+type Row = (usize, usize, Timestamp);
+
+impl Graph {
+    /// Relabel the transactions in a canonical topological order, so that two numberings of
+    /// the same graph give the same result. Transactions are numbered once both of their
+    /// references are, by their timestamp and then by the new IDs of their references.
+    /// Remaining ties are broken by colors refined from the transactions around them, and
+    /// when colors tie as well, by trying each tied transaction in turn and keeping the
+    /// smallest result. Returns None when the graph is cyclic.
+    pub fn canonical(&self) -> Option<Graph> {
+        let colors = self
+            .transactions()
+            .map(|t| {
+                let mut hash = Fnv1a::new();
+                hash.write(t.timestamp());
+                (t.id(), hash.finish())
+            })
+            .collect();
+        let rows = self.canonical_rows(&self.refine(colors))?;
+
+        let mut graph = Graph::with_capacity(self.len());
+        for (i, (left, right, timestamp)) in rows.into_iter().enumerate() {
+            let id = NonRootId::try_from(i + 2).expect("IDs start at 2");
+            let left = Id::try_from(left).expect("References are valid IDs");
+            let right = Id::try_from(right).expect("References are valid IDs");
+            graph.push(Transaction::new(id, left, right, timestamp));
+        }
+
+        *graph.metadata_mut() = self.metadata().clone();
+        Some(graph)
+    }
+
+    /// A hash of the structure of the graph that does not depend on the numbering of its
+    /// transactions nor on its metadata. Returns None when the graph is cyclic.
+    pub fn canonical_hash(&self) -> Option<u64> {
+        let graph = self.canonical()?;
+        let mut hash = Fnv1a::new();
+
+        hash.write(graph.len() as u64);
+        for t in graph.transactions() {
+            hash.write(usize::from(t.left()) as u64);
+            hash.write(usize::from(t.right()) as u64);
+            hash.write(t.timestamp());
+        }
+
+        Some(hash.finish())
+    }
+
+    /// Number the transactions in topological order, by their sort key. When transactions
+    /// that are not twins tie, number the graph once per tied transaction, with that
+    /// transaction colored apart, and keep the smallest result. Returns None when the graph
+    /// is cyclic.
+    fn canonical_rows(&self, colors: &Colors) -> Option<Vec<Row>> {
+        let mut ids: Map<NonRootId, NonRootId> = Map::with_capacity(self.len());
+
+        let relabel = |ids: &Map<NonRootId, NonRootId>, id: Id| match id {
+            Id::Transaction(id) => ids.get(&id).map(|&id| Id::from(id)),
+            Id::Root => Some(Id::Root),
+        };
+
+        // The transactions whose references are all numbered, by their sort key.
+        let mut ready: BinaryHeap<Reverse<(Key, usize)>> = BinaryHeap::new();
+        let mut n_missing: Map<NonRootId, usize> = Map::with_capacity(self.len());
+
+        for t in self.transactions() {
+            let missing = self.parents(t).count();
+            n_missing.insert(t.id(), missing);
+            if missing == 0 {
+                let key = (t.timestamp(), 1, 1, colors[&t.id()]);
+                ready.push(Reverse((key, t.id().into())));
+            }
+        }
+
+        let mut rows = Vec::with_capacity(self.len());
+
+        while let Some(Reverse((key, old))) = ready.pop() {
+            let old = NonRootId::try_from(old).expect("Transactions have valid IDs");
+
+            // The transactions tied with this one, one per set of twins.
+            let mut tied = vec![old];
+            let mut twins = Vec::new();
+            while let Some(Reverse((next, id))) = ready.peek() {
+                if *next != key {
+                    break;
+                }
+                let id = NonRootId::try_from(*id).expect("Transactions have valid IDs");
+                if tied.iter().any(|&other| self.are_twins(other, id)) {
+                    twins.push(id);
+                } else {
+                    tied.push(id);
+                }
+                ready.pop();
+            }
+
+            if tied.len() > 1 {
+                // Leave out the transactions that an automorphism maps to one already tried,
+                // since they give the same result.
+                let mut tried: Vec<Colors> = Vec::with_capacity(tied.len());
+                let mut results: Vec<Vec<Row>> = Vec::with_capacity(tied.len());
+
+                for id in tied {
+                    let mut apart = colors.clone();
+                    let color = apart.get_mut(&id).expect("Tied IDs are transactions");
+                    let mut hash = Fnv1a::new();
+                    hash.write(*color);
+                    *color = hash.finish();
+                    let apart = self.refine(apart);
+
+                    if tried
+                        .iter()
+                        .any(|other| self.is_automorphism(colors, other, &apart))
+                    {
+                        continue;
+                    }
+
+                    results.push(self.canonical_rows(&apart)?);
+                    tried.push(apart);
+                }
+
+                return results.into_iter().min();
+            }
+
+            // Any twin of this transaction can be numbered next, in any order.
+            for twin in twins {
+                ready.push(Reverse((key, twin.into())));
+            }
+
+            let id = NonRootId::try_from(rows.len() + 2).expect("IDs start at 2");
+            ids.insert(old, id);
+            let (timestamp, left, right, _) = key;
+            rows.push((left, right, timestamp));
+
+            // Release the transactions that were only waiting for this one.
+            for child in self.children(old) {
+                let missing = n_missing
+                    .get_mut(&child)
+                    .expect("Children are transactions");
+                *missing -= 1;
+
+                if *missing == 0 {
+                    let t = &self[child];
+                    let left = relabel(&ids, t.left()).expect("References are numbered");
+                    let right = relabel(&ids, t.right()).expect("References are numbered");
+                    let key = (t.timestamp(), left.into(), right.into(), colors[&child]);
+                    ready.push(Reverse((key, child.into())));
+                }
+            }
+        }
+
+        if rows.len() < self.len() {
+            return None;
+        }
+
+        Some(rows)
+    }
+
+    /// Refine the colors of the transactions until they stop splitting. Each round hashes
+    /// the color of a transaction with the colors of its references and with the sorted
+    /// colors of the transactions referring to it, along with which reference refers to it.
+    fn refine(&self, mut colors: Colors) -> Colors {
+        let count = |colors: &Colors| colors.values().collect::<Set<_>>().len();
+        let mut n_colors = count(&colors);
+
+        loop {
+            let refined: Colors = self
+                .transactions()
+                .map(|t| {
+                    let mut hash = Fnv1a::new();
+                    hash.write(colors[&t.id()]);
+                    for reference in [t.left(), t.right()].iter() {
+                        hash.write(match reference {
+                            Id::Transaction(id) => colors[id],
+                            Id::Root => 0,
+                        });
+                    }
+                    let mut children: Vec<u64> = self
+                        .children_sides(t.id())
+                        .into_iter()
+                        .map(|(child, side)| colors[&child] ^ side)
+                        .collect();
+                    children.sort_unstable();
+                    for child in children {
+                        hash.write(child);
+                    }
+                    (t.id(), hash.finish())
+                })
+                .collect();
+
+            let n_refined = count(&refined);
+            if n_refined == n_colors {
+                return colors;
+            }
+            colors = refined;
+            n_colors = n_refined;
+        }
+    }
+
+    /// Whether matching the transactions of the same color in two refinements of `colors`
+    /// gives an automorphism of the graph that keeps `colors`. Transactions that have the
+    /// same color in both refinements are matched with themselves, and the others in order.
+    fn is_automorphism(&self, colors: &Colors, from: &Colors, to: &Colors) -> bool {
+        let classes = |refined: &Colors| {
+            let mut classes: Map<u64, Vec<usize>> = Map::new();
+            for (&id, &color) in refined {
+                classes.entry(color).or_default().push(id.into());
+            }
+            for class in classes.values_mut() {
+                class.sort_unstable();
+            }
+            classes
+        };
+        let (from, to) = (classes(from), classes(to));
+
+        let mut mapping: Map<usize, usize> = Map::with_capacity(self.len() + 1);
+        mapping.insert(Id::Root.into(), Id::Root.into());
+        for (color, class) in from {
+            let other = match to.get(&color) {
+                Some(other) if other.len() == class.len() => other,
+                _ => return false,
+            };
+            let shared: Set<usize> = class.iter().copied().collect();
+            let shared: Set<usize> = other
+                .iter()
+                .copied()
+                .filter(|id| shared.contains(id))
+                .collect();
+
+            mapping.extend(shared.iter().map(|&id| (id, id)));
+            let rest = class.iter().filter(|id| !shared.contains(id));
+            let other_rest = other.iter().filter(|id| !shared.contains(id));
+            mapping.extend(rest.copied().zip(other_rest.copied()));
+        }
+
+        self.transactions().all(|t| {
+            let id = NonRootId::try_from(mapping[&t.id().into()]).expect("Mapped IDs are valid");
+            let image = &self[id];
+            colors[&t.id()] == colors[&id]
+                && t.timestamp() == image.timestamp()
+                && mapping[&t.left().into()] == image.left().into()
+                && mapping[&t.right().into()] == image.right().into()
+        })
+    }
+
+    /// Whether two transactions with the same references are also referred to by the same
+    /// transactions in the same way, so that swapping them leaves the graph as is.
+    fn are_twins(&self, a: NonRootId, b: NonRootId) -> bool {
+        self.children_sides(a) == self.children_sides(b)
+    }
+
+    /// The references of a transaction, once per referenced transaction.
+    fn parents<'a>(&self, t: &'a Transaction) -> impl Iterator<Item = NonRootId> + 'a {
+        let left = t.left();
+        let right = t.right();
+        let right = if right == left { None } else { Some(right) };
+
+        Some(left)
+            .into_iter()
+            .chain(right)
+            .filter_map(|id| match id {
+                Id::Transaction(id) => Some(id),
+                Id::Root => None,
+            })
+    }
+
+    /// The transactions referring to a transaction, once per referring transaction.
+    fn children(&self, id: NonRootId) -> Vec<NonRootId> {
+        self.references(Id::from(id))
+            .map(|references| references.sources().copied().collect())
+            .unwrap_or_default()
+    }
+
+    /// The transactions referring to a transaction, sorted, along with which of their
+    /// references refers to it: 1 for the left one, 2 for the right one and 3 for both.
+    fn children_sides(&self, id: NonRootId) -> Vec<(NonRootId, u64)> {
+        let mut children: Vec<(NonRootId, u64)> = self
+            .children(id)
+            .into_iter()
+            .map(|child| {
+                let t = &self[child];
+                let side =
+                    u64::from(t.left() == Id::from(id)) | u64::from(t.right() == Id::from(id)) << 1;
+                (child, side)
+            })
+            .collect();
+        children.sort_unstable_by_key(|&(child, side)| (usize::from(child), side));
+        children
+    }
+}
+
+#[cfg(test)]
+mod canonical_tests {
+    use crate::graph::{test_graph, Graph};
+
+    fn write(graph: &Graph) -> String {
+        let mut output = Vec::new();
+        graph.write(&mut output).unwrap();
+        String::from_utf8(output).unwrap()
+    }
+
+    #[test]
+    fn relabel() {
+        // The same graph numbered in two different ways, with forward references.
+        let a = test_graph("5\n1 1 10\n1 1 10\n3 2 20\n6 2 30\n4 1 25\n");
+        let b = test_graph("5\n4 5 30\n1 1 10\n6 1 25\n1 1 10\n3 5 20\n");
+
+        let expected = "5\n1 1 10\n1 1 10\n2 3 20\n4 1 25\n5 3 30\n";
+        assert_eq!(write(&a.canonical().unwrap()), expected);
+        assert_eq!(write(&b.canonical().unwrap()), expected);
+        assert_eq!(a.canonical_hash(), b.canonical_hash());
+    }
+
+    #[test]
+    fn renumbered_ties() {
+        // Two transactions that only differ by the transactions referring to them, which are
+        // numbered the other way round in the second graph.
+        let a = test_graph("6\n1 1 5\n1 1 6\n1 1 10\n1 1 10\n4 2 30\n5 3 30\n");
+        let b = test_graph("6\n1 1 5\n1 1 6\n1 1 10\n1 1 10\n5 2 30\n4 3 30\n");
+        assert_eq!(a.canonical(), b.canonical());
+        assert_eq!(a.canonical_hash(), b.canonical_hash());
+
+        // Ties that colors cannot break: two chains of transactions that are alike, numbered
+        // the other way round in the second graph.
+        let a = test_graph("6\n1 1 10\n1 1 10\n2 1 20\n3 1 20\n4 1 30\n5 1 30\n");
+        let b = test_graph("6\n1 1 10\n1 1 10\n3 1 20\n2 1 20\n5 1 30\n4 1 30\n");
+        let expected = "6\n1 1 10\n1 1 10\n2 1 20\n3 1 20\n4 1 30\n5 1 30\n";
+        assert_eq!(write(&a.canonical().unwrap()), expected);
+        assert_eq!(write(&b.canonical().unwrap()), expected);
+        assert_eq!(a.canonical_hash(), b.canonical_hash());
+    }
+
+    #[test]
+    fn distinct() {
+        let a = test_graph("3\n1 1 10\n2 1 20\n3 2 30\n");
+        let b = test_graph("3\n1 1 10\n2 1 20\n2 3 30\n");
+        let c = test_graph("3\n1 1 10\n2 1 20\n3 2 31\n");
+        assert_ne!(a.canonical_hash(), b.canonical_hash());
+        assert_ne!(a.canonical_hash(), c.canonical_hash());
+        assert_eq!(a.canonical_hash(), a.canonical().unwrap().canonical_hash());
+    }
+
+    #[test]
+    fn cyclic() {
+        let a = test_graph("3\n1 1 10\n4 2 20\n3 2 30\n");
+        assert_eq!(a.canonical(), None);
+        assert_eq!(a.canonical_hash(), None);
+    }
+}
@@ -0,0 +1,147 @@
+#![warn(clippy::all)]
+
+//! Comparison of two snapshots of the same ledger taken at different times.
+
+use crate::graph::Graph;
+use crate::stats::{self, StatResult};
+use crate::transaction::Transaction;
+use conv::errors::PosOverflow;
+use derive_more::Display;
+use std::fmt::{self, Display};
+
+/// A transaction of the old snapshot that the new snapshot does not agree with.
+#[derive(PartialEq, Eq, Debug)]
+pub struct Conflict<'a> {
+    pub old: &'a Transaction,
+
+    /// The transaction with the same ID in the new snapshot, if any.
+    pub new: Option<&'a Transaction>,
+}
+
+impl Display for Conflict<'_> {
+    fn fmt(&self, f: &mut fmt::Formatter) -> fmt::Result {
+        match self.new {
+            Some(new) => write!(f, "{} became {}", self.old, new),
+            None => write!(f, "{} is missing", self.old),
+        }
+    }
+}
+
+/// How a value of a statistic changed between two snapshots.
+#[derive(PartialEq, Debug, Display)]
+#[display(fmt = "> {}: {:.2} -> {:.2} ({:+.2})", name, old, new, "new - old")]
+pub struct StatChange {
+    pub name: &'static str,
+    pub old: f64,
+    pub new: f64,
+}
+
+impl StatChange {
+    /// The changes of the values between the old and new results of the same statistics.
+    pub fn between(old: &[Box<dyn StatResult>], new: &[Box<dyn StatResult>]) -> Vec<Self> {
+        old.iter()
+            .zip(new.iter())
+            .flat_map(|(old, new)| old.values().into_iter().zip(new.values()))
+            .map(|((name, old), (_, new))| StatChange { name, old, new })
+            .collect()
+    }
+}
+
+/// The differences between an old and a new snapshot of the same ledger. The old snapshot
+/// is expected to be a prefix of the new one: transactions with the same ID have the same
+/// references and timestamps, and the new snapshot only adds transactions after them.
+pub struct Diff<'a> {
+    old: &'a Graph,
+    new: &'a Graph,
+    conflicts: Vec<Conflict<'a>>,
+}
+
+impl<'a> Diff<'a> {
+    pub fn new(old: &'a Graph, new: &'a Graph) -> Self {
+        let mut new_transactions = new.transactions();
+        let conflicts = old
+            .transactions()
+            .filter_map(|old| match new_transactions.next() {
+                Some(new) if new == old => None,
+                new => Some(Conflict { old, new }),
+            })
+            .collect();
+
+        Self {
+            old,
+            new,
+            conflicts,
+        }
+    }
+
+    /// Whether the old snapshot is a prefix of the new one.
+    pub fn is_prefix(&self) -> bool {
+        self.conflicts.is_empty()
+    }
+
+    /// The transactions of the old snapshot that the new snapshot does not agree with.
+    pub fn conflicts(&self) -> &[Conflict<'a>] {
+        &self.conflicts
+    }
+
+    /// The transactions of the new snapshot beyond the ones of the old snapshot.
+    pub fn added(&self) -> impl Iterator<Item = &'a Transaction> {
+        self.new.transactions().skip(self.old.len())
+    }
+
+    /// How the values of every statistic changed between the two snapshots.
+    pub fn stat_changes(&self) -> Result<Vec<StatChange>, PosOverflow<usize>> {
+        let old = stats::results(self.old)?;
+        let new = stats::results(self.new)?;
+        Ok(StatChange::between(&old, &new))
+    }
+}
+
+#[cfg(test)]
+mod diff_tests {
+    use super::Diff;
+    use crate::graph::test_graph;
+
+    #[test]
+    fn prefix() {
+        let old = test_graph("2\n1 1 120\n2 1 130\n");
+        let new = test_graph("4\n1 1 120\n2 1 130\n3 2 140\n4 4 150\n");
+        let diff = Diff::new(&old, &new);
+        assert!(diff.is_prefix());
+
+        let added: Vec<String> = diff.added().map(ToString::to_string).collect();
+        assert_eq!(
+            added,
+            vec![
+                "Tx<Id(4), Tx:Id(3), Tx:Id(2), 140>",
+                "Tx<Id(5), Tx:Id(4), Tx:Id(4), 150>"
+            ]
+        );
+
+        let changes = diff.stat_changes().unwrap();
+        assert_eq!(changes.len(), 6);
+        assert_eq!(
+            changes[0].to_string(),
+            "> AVG DAG DEPTH: 0.67 -> 1.40 (+0.73)"
+        );
+        assert_eq!(changes[2].to_string(), "> AVG REF: 1.33 -> 1.60 (+0.27)");
+    }
+
+    #[test]
+    fn conflicts() {
+        let old = test_graph("3\n1 1 120\n2 1 130\n2 2 140\n");
+        let new = test_graph("2\n1 1 120\n2 2 130\n");
+        let diff = Diff::new(&old, &new);
+        assert!(!diff.is_prefix());
+        assert_eq!(diff.added().count(), 0);
+
+        let conflicts: Vec<String> = diff.conflicts().iter().map(ToString::to_string).collect();
+        assert_eq!(
+            conflicts,
+            vec![
+                "Tx<Id(3), Tx:Id(2), Root, 130> became Tx<Id(3), Tx:Id(2), Tx:Id(2), 130>",
+                "Tx<Id(4), Tx:Id(2), Tx:Id(2), 140> is missing",
+            ]
+        );
+    }
+}
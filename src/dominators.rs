@@ -0,0 +1,222 @@
+#![warn(clippy::all)]
+
+//! The dominator tree of a `Graph` relative to the Root: a transaction dominates another
+//! one when every path from the other one to the Root passes through it. Transactions
+//! dominating others are the fragility points of a ledger, since losing them disconnects
+//! the transactions they dominate from the Root.
+
+use crate::graph::Graph;
+use crate::id::{Id, NonRootId};
+use derive_more::Display;
+use std::collections::HashMap as Map;
+use std::collections::HashSet as Set;
+use std::convert::TryFrom;
+
+/// A transaction whose removal disconnects other transactions from the Root, displayed as
+/// a tab-separated row.
+#[derive(PartialEq, Debug, Display)]
+#[display(fmt = "{}\t{}", id, dominated)]
+pub struct Articulation {
+    /// The ID of the transaction.
+    pub id: usize,
+
+    /// The number of transactions it dominates, not including itself.
+    pub dominated: usize,
+}
+
+/// The dominator tree of a `Graph`, built by `Graph::dominators`.
+pub struct Dominators {
+    /// The immediate dominator of each transaction, the closest of the transactions every
+    /// path to the Root passes through.
+    immediate: Map<NonRootId, Id>,
+
+    /// The transactions, each after the transactions it refers to.
+    order: Vec<NonRootId>,
+}
+
+impl Dominators {
+    /// The ID of the immediate dominator of a transaction, 1 for the Root. Returns `None`
+    /// for the Root and unknown transactions.
+    pub fn immediate(&self, id: usize) -> Option<usize> {
+        let id = NonRootId::try_from(id).ok()?;
+        self.immediate.get(&id).map(|&dominator| dominator.into())
+    }
+
+    /// The IDs of the transactions every path from a transaction to the Root passes
+    /// through, from the closest to the farthest, leaving out the transaction and the
+    /// Root.
+    pub fn chain(&self, id: usize) -> Vec<usize> {
+        let mut chain = Vec::new();
+        let mut next = NonRootId::try_from(id)
+            .ok()
+            .and_then(|id| self.immediate.get(&id));
+
+        while let Some(&Id::Transaction(dominator)) = next {
+            chain.push(dominator.into());
+            next = self.immediate.get(&dominator);
+        }
+
+        chain
+    }
+
+    /// The transactions that dominate at least one other transaction, from the one
+    /// dominating the most transactions to the one dominating the fewest.
+    pub fn articulations(&self) -> Vec<Articulation> {
+        // Transactions come after their dominators in the order, so adding the subtree
+        // sizes backwards completes each of them before it is added to its dominator.
+        let mut sizes: Map<NonRootId, usize> = Map::with_capacity(self.order.len());
+        for id in self.order.iter().rev() {
+            let size = sizes.entry(*id).or_insert(0);
+            *size += 1;
+            let size = *size;
+            if let Id::Transaction(dominator) = self.immediate[id] {
+                *sizes.entry(dominator).or_insert(0) += size;
+            }
+        }
+
+        let mut articulations: Vec<Articulation> = sizes
+            .into_iter()
+            .filter(|&(_, size)| size > 1)
+            .map(|(id, size)| Articulation {
+                id: id.into(),
+                dominated: size - 1,
+            })
+            .collect();
+
+        articulations.sort_by(|a, b| b.dominated.cmp(&a.dominated).then(a.id.cmp(&b.id)));
+        articulations
+    }
+}
+
+impl Graph {
+    /// Build the dominator tree of the graph. In an acyclic graph, the immediate dominator
+    /// of a transaction is the nearest common dominator of the transactions it refers to,
+    /// so transactions are visited after their parents. Assumes the graph is acyclic.
+    pub fn dominators(&self) -> Dominators {
+        let order = self.topological_order();
+        let mut immediate: Map<NonRootId, Id> = Map::with_capacity(order.len());
+        let mut levels: Map<NonRootId, usize> = Map::with_capacity(order.len());
+
+        for &id in &order {
+            let t = &self[id];
+            let dominator = intersect(t.left(), t.right(), &immediate, &levels);
+            let level = match dominator {
+                Id::Transaction(dominator) => levels[&dominator] + 1,
+                Id::Root => 1,
+            };
+            immediate.insert(id, dominator);
+            levels.insert(id, level);
+        }
+
+        Dominators { immediate, order }
+    }
+
+    /// The transactions, each after the transactions it refers to.
+    fn topological_order(&self) -> Vec<NonRootId> {
+        let mut order = Vec::with_capacity(self.len());
+        let mut visited = Set::with_capacity(self.len());
+
+        for t in self.transactions() {
+            let mut stack = vec![(t.id(), false)];
+            while let Some((id, expanded)) = stack.pop() {
+                if expanded {
+                    order.push(id);
+                    continue;
+                }
+                if !visited.insert(id) {
+                    continue;
+                }
+
+                stack.push((id, true));
+                let t = &self[id];
+                for parent in [t.left(), t.right()] {
+                    if let Id::Transaction(parent) = parent {
+                        if !visited.contains(&parent) {
+                            stack.push((parent, false));
+                        }
+                    }
+                }
+            }
+        }
+
+        order
+    }
+}
+
+/// The nearest common dominator of two transactions, found by walking up the dominator tree
+/// from the deeper one. `levels` holds the depth of each transaction in the tree.
+fn intersect(
+    mut a: Id,
+    mut b: Id,
+    immediate: &Map<NonRootId, Id>,
+    levels: &Map<NonRootId, usize>,
+) -> Id {
+    let level = |id: Id| match id {
+        Id::Transaction(id) => levels[&id],
+        Id::Root => 0,
+    };
+    let up = |id: Id| match id {
+        Id::Transaction(id) => immediate[&id],
+        Id::Root => Id::Root,
+    };
+
+    while a != b {
+        if level(a) >= level(b) {
+            a = up(a);
+        } else {
+            b = up(b);
+        }
+    }
+
+    a
+}
+
+#[cfg(test)]
+mod dominators_tests {
+    use crate::graph::test_graph;
+
+    #[test]
+    fn dominators() {
+        // Every path from transactions 3 to 6 to the Root passes through transaction 2,
+        // and every path from transaction 5 passes through transaction 4.
+        let tangle = test_graph("7\n1 1 0\n2 2 1\n3 2 2\n4 4 3\n5 3 4\n1 1 5\n7 6 6\n");
+        let dominators = tangle.dominators();
+
+        let immediate: Vec<Option<usize>> = (1..=9).map(|id| dominators.immediate(id)).collect();
+        assert_eq!(
+            immediate,
+            vec![
+                None,
+                Some(1),
+                Some(2),
+                Some(2),
+                Some(4),
+                Some(2),
+                Some(1),
+                Some(1),
+                None
+            ]
+        );
+
+        assert_eq!(dominators.chain(5), vec![4, 2]);
+        assert_eq!(dominators.chain(6), vec![2]);
+        assert_eq!(dominators.chain(8), Vec::<usize>::new());
+        assert_eq!(dominators.chain(1), Vec::<usize>::new());
+
+        let articulations: Vec<String> = dominators
+            .articulations()
+            .iter()
+            .map(ToString::to_string)
+            .collect();
+        assert_eq!(articulations, vec!["2\t4", "4\t1"]);
+    }
+
+    #[test]
+    fn no_articulations() {
+        // Transactions referring to the Root and to another transaction can always bypass
+        // it.
+        let tangle = test_graph("3\n1 1 0\n2 1 1\n3 1 2\n");
+        assert!(tangle.dominators().articulations().is_empty());
+        assert!(test_graph("0\n").dominators().articulations().is_empty());
+    }
+}
@@ -0,0 +1,242 @@
+#![warn(clippy::all)]
+
+//! Extraction of subgraphs into standalone graphs with dense IDs.
+
+use crate::graph::Graph;
+use crate::id::{Id, NonRootId};
+use crate::time::Timestamp;
+use crate::transaction::Transaction;
+use std::collections::{HashMap as Map, HashSet as Set};
+use std::convert::TryFrom;
+use std::ops::Range;
+
+/// The transactions to extract from a `Graph`.
+#[derive(Clone, PartialEq, Eq, Debug)]
+pub enum Selection {
+    /// The transactions with a timestamp in the range.
+    Timestamps(Range<Timestamp>),
+
+    /// The transactions with a depth in the range.
+    Depths(Range<usize>),
+
+    /// The transactions with an ID in the range.
+    Ids(Range<usize>),
+
+    /// The transaction with the ID and the transactions it refers to, directly or not.
+    Past(usize),
+
+    /// The transaction with the ID and the transactions referring to it, directly or not.
+    Future(usize),
+}
+
+/// What to do with the references of extracted transactions to transactions that are not
+/// extracted.
+#[derive(Clone, Copy, PartialEq, Eq, Debug)]
+pub enum Policy {
+    /// Redirect the references to the Root.
+    Root,
+
+    /// Leave out the transactions with such references, along with the transactions
+    /// referring to them, directly or not.
+    Drop,
+}
+
+impl Graph {
+    /// Extract the selected transactions into a new `Graph`. The transactions keep their
+    /// order and are renumbered so that their IDs are dense again, and references to
+    /// transactions that are not extracted are handled according to `policy`. Cones of
+    /// transactions that are not in the `Graph` are empty.
+    pub fn extract(&self, selection: &Selection, policy: Policy) -> Graph {
+        let mut extracted = self.select(selection);
+
+        if policy == Policy::Drop {
+            self.drop_leaving(&mut extracted);
+        }
+
+        self.subgraph(&extracted, &Set::new())
+    }
+
+    /// The `Graph` of the `kept` transactions, renumbered like `Graph::extract`. References
+    /// to transactions that are not kept, and all references of the `detached`
+    /// transactions, point to the Root instead.
+    pub(crate) fn subgraph(&self, kept: &Set<NonRootId>, detached: &Set<NonRootId>) -> Graph {
+        // Assign the new IDs first, since references can point to later transactions.
+        let ids: Map<NonRootId, NonRootId> = self
+            .transactions()
+            .map(Transaction::id)
+            .filter(|id| kept.contains(id))
+            .enumerate()
+            .map(|(i, id)| (id, NonRootId::try_from(i + 2).expect("IDs start at 2")))
+            .collect();
+
+        let renumber = |id: Id| match id {
+            Id::Transaction(id) => ids.get(&id).map_or(Id::Root, |&id| Id::from(id)),
+            Id::Root => Id::Root,
+        };
+
+        let mut graph = Graph::with_capacity(ids.len());
+        for t in self.transactions().filter(|t| kept.contains(&t.id())) {
+            let id = ids[&t.id()];
+            let t = if detached.contains(&t.id()) {
+                Transaction::new(id, Id::Root, Id::Root, t.timestamp())
+            } else {
+                Transaction::new(id, renumber(t.left()), renumber(t.right()), t.timestamp())
+            };
+            graph.push(t);
+        }
+
+        *graph.metadata_mut() = self.metadata().clone();
+        graph
+    }
+
+    fn select(&self, selection: &Selection) -> Set<NonRootId> {
+        match selection {
+            Selection::Timestamps(range) => self
+                .transactions()
+                .filter(|t| range.contains(&t.timestamp()))
+                .map(Transaction::id)
+                .collect(),
+            Selection::Depths(range) => {
+                let mut cache = Map::with_capacity(self.len());
+                self.transactions()
+                    .map(Transaction::id)
+                    .filter(|&id| range.contains(&self.depth(id, &mut cache)))
+                    .collect()
+            }
+            Selection::Ids(range) => self
+                .transactions()
+                .map(Transaction::id)
+                .filter(|&id| range.contains(&usize::from(id)))
+                .collect(),
+            Selection::Past(id) => self.cone(*id, |id| {
+                let t = &self[id];
+                [t.left(), t.right()]
+                    .iter()
+                    .filter_map(|&id| match id {
+                        Id::Transaction(id) => Some(id),
+                        Id::Root => None,
+                    })
+                    .collect()
+            }),
+            Selection::Future(id) => self.cone(*id, |id| {
+                self.references(Id::from(id))
+                    .map(|references| references.sources().copied().collect())
+                    .unwrap_or_default()
+            }),
+        }
+    }
+
+    /// The transaction with the ID and the transactions reachable from it through `next`.
+    fn cone<F>(&self, id: usize, next: F) -> Set<NonRootId>
+    where
+        F: Fn(NonRootId) -> Vec<NonRootId>,
+    {
+        let mut cone = Set::new();
+
+        let id = match NonRootId::try_from(id) {
+            Ok(id) if usize::from(id) <= self.len() + 1 => id,
+            _ => return cone,
+        };
+
+        let mut stack = vec![id];
+        while let Some(id) = stack.pop() {
+            if cone.insert(id) {
+                stack.extend(next(id));
+            }
+        }
+
+        cone
+    }
+
+    /// Remove the transactions with references to transactions that are not extracted,
+    /// and then the transactions referring to them.
+    fn drop_leaving(&self, extracted: &mut Set<NonRootId>) {
+        let is_leaving = |id: Id| match id {
+            Id::Transaction(id) => !extracted.contains(&id),
+            Id::Root => false,
+        };
+
+        let mut stack: Vec<NonRootId> = self
+            .transactions()
+            .filter(|t| extracted.contains(&t.id()))
+            .filter(|t| is_leaving(t.left()) || is_leaving(t.right()))
+            .map(Transaction::id)
+            .collect();
+
+        while let Some(id) = stack.pop() {
+            if extracted.remove(&id) {
+                if let Some(references) = self.references(Id::from(id)) {
+                    stack.extend(references.sources());
+                }
+            }
+        }
+    }
+}
+
+#[cfg(test)]
+mod extract_tests {
+    use super::{Policy, Selection};
+    use crate::graph::Graph;
+    use std::convert::TryFrom;
+    use std::io::BufReader;
+
+    // Transaction 4 refers to transaction 6 that comes after it.
+    const INPUT: &str = "source: test\n5\n1 1 100\n2 1 110\n6 2 120\n3 4 130\n3 2 140\n";
+
+    fn extract(selection: Selection, policy: Policy) -> String {
+        let graph = Graph::try_from(BufReader::new(INPUT.as_bytes())).unwrap();
+        let extracted = graph.extract(&selection, policy);
+
+        let mut output = Vec::new();
+        extracted.write(&mut output).unwrap();
+        let output = String::from_utf8(output).unwrap();
+
+        // The output is a valid graph.
+        let reread = Graph::try_from(BufReader::new(output.as_bytes())).unwrap();
+        assert_eq!(reread, extracted);
+
+        output
+    }
+
+    #[test]
+    fn timestamps() {
+        let output = extract(Selection::Timestamps(110..131), Policy::Root);
+        assert_eq!(output, "source: test\n3\n1 1 110\n1 1 120\n2 3 130\n");
+
+        let output = extract(Selection::Timestamps(110..131), Policy::Drop);
+        assert_eq!(output, "source: test\n0\n");
+
+        let output = extract(Selection::Timestamps(100..131), Policy::Drop);
+        assert_eq!(output, "source: test\n2\n1 1 100\n2 1 110\n");
+    }
+
+    #[test]
+    fn depths_and_ids() {
+        let output = extract(Selection::Depths(1..2), Policy::Root);
+        assert_eq!(output, "source: test\n2\n1 1 100\n2 1 110\n");
+
+        let output = extract(Selection::Ids(4..7), Policy::Root);
+        assert_eq!(output, "source: test\n3\n4 1 120\n1 2 130\n1 1 140\n");
+    }
+
+    #[test]
+    fn cones() {
+        let output = extract(Selection::Past(4), Policy::Drop);
+        assert_eq!(
+            output,
+            "source: test\n4\n1 1 100\n2 1 110\n5 2 120\n3 2 140\n"
+        );
+
+        let output = extract(Selection::Future(3), Policy::Root);
+        assert_eq!(
+            output,
+            "source: test\n4\n1 1 110\n5 1 120\n2 3 130\n2 1 140\n"
+        );
+
+        let output = extract(Selection::Future(3), Policy::Drop);
+        assert_eq!(output, "source: test\n0\n");
+
+        let output = extract(Selection::Past(9), Policy::Root);
+        assert_eq!(output, "source: test\n0\n");
+    }
+}
@@ -0,0 +1,213 @@
+#![warn(clippy::all)]
+
+//! Incremental reading of graphs from inputs that keep being appended to.
+
+use crate::graph::{self, Error, Graph};
+use crate::incremental::{ObservedGraph, Observer};
+use crate::input::byte_lines;
+use crate::location::Location;
+use crate::metadata::Metadata;
+use crate::transaction::Transaction;
+use std::io::{self, Read};
+use std::mem;
+use std::str;
+
+/// Reads `LID RID TIMESTAMP` lines as they are appended to an input, pushing the new
+/// transactions to a `Graph` that is kept in memory. The header of the input is optional
+/// and its number of transactions is ignored, since the input is never expected to end.
+pub struct Follower<R> {
+    input: R,
+
+    /// The graph of the transactions read so far.
+    graph: ObservedGraph,
+
+    /// The bytes read after the last complete line, waiting for the rest of their line.
+    partial: Vec<u8>,
+
+    /// The number of complete lines read so far.
+    line_no: usize,
+
+    /// Whether the header lines might still be read, which is only the case until the
+    /// first transaction.
+    in_header: bool,
+}
+
+impl<R: Read> Follower<R> {
+    pub fn new(input: R) -> Self {
+        Self {
+            input,
+            graph: ObservedGraph::new(Graph::with_capacity(0)),
+            partial: Vec::new(),
+            line_no: 0,
+            in_header: true,
+        }
+    }
+
+    pub fn graph(&self) -> &Graph {
+        self.graph.graph()
+    }
+
+    /// The graph of the transactions read so far along with the statistics that follow it.
+    pub fn observed(&self) -> &ObservedGraph {
+        &self.graph
+    }
+
+    /// Subscribe a statistic to the transactions read from the input.
+    pub fn subscribe(&mut self, observer: Box<dyn Observer>) {
+        self.graph.subscribe(observer);
+    }
+
+    /// Read the lines appended to the input since the last poll and push their transactions
+    /// to the `Graph`, returning the number of new transactions. An incomplete last line is
+    /// kept until the rest of it is appended.
+    pub fn poll(&mut self) -> Result<usize, Error> {
+        self.input.read_to_end(&mut self.partial)?;
+
+        let end = match self.partial.iter().rposition(|&b| b == b'\n') {
+            Some(newline) => newline + 1,
+            None => return Ok(0),
+        };
+
+        let rest = self.partial.split_off(end);
+        let lines = mem::replace(&mut self.partial, rest);
+        let n_transactions = self.graph.graph().len();
+
+        for (line_no, line) in byte_lines(&lines) {
+            self.read_line(self.line_no + line_no, line)?;
+        }
+
+        self.line_no += lines.iter().filter(|&&b| b == b'\n').count();
+        Ok(self.graph.graph().len() - n_transactions)
+    }
+
+    fn read_line(&mut self, line_no: usize, line: &[u8]) -> Result<(), Error> {
+        if self.in_header {
+            let text =
+                str::from_utf8(line).map_err(|e| io::Error::new(io::ErrorKind::InvalidData, e))?;
+
+            // Metadata and the number of transactions are on lines of their own, anything
+            // else is the first transaction. Keys of metadata are single tokens, unlike the
+            // text before the colons of RFC 3339 timestamps.
+            let is_metadata = Metadata::split_line(text)
+                .is_some_and(|(key, _)| key.split_ascii_whitespace().count() == 1);
+            let is_header = is_metadata || text.split_ascii_whitespace().count() == 1;
+
+            if is_header {
+                let n_transactions =
+                    graph::read_header_line(line_no, text, self.graph.metadata_mut())?;
+                self.in_header = n_transactions.is_none();
+                return Ok(());
+            }
+
+            self.in_header = false;
+        }
+
+        let id = self.graph.graph().len() + 2;
+        let t = Transaction::parse_bytes(id, line)
+            .map_err(|e| graph::transaction_error(line_no, &String::from_utf8_lossy(line), e))?;
+        if line.contains(&b':') {
+            let text = String::from_utf8_lossy(line);
+            graph::check_date_time(line_no, &text, 2, self.graph.metadata_mut())?;
+        }
+
+        // Without a number of transactions upfront, references can only point to the
+        // transactions that were already read.
+        let max = id - 1;
+
+        let left: usize = t.left().into();
+        if left > max {
+            let location = Location::token(line_no, &String::from_utf8_lossy(line), 0);
+            return Err(Error::InvalidLeft(location, t.id(), t.left(), max));
+        }
+
+        let right: usize = t.right().into();
+        if right > max {
+            let location = Location::token(line_no, &String::from_utf8_lossy(line), 1);
+            return Err(Error::InvalidRight(location, t.id(), t.right(), max));
+        }
+
+        self.graph.push(t);
+        Ok(())
+    }
+}
+
+#[cfg(test)]
+mod follow_tests {
+    use super::Follower;
+    use crate::graph::{Error, Graph};
+    use std::convert::TryFrom;
+    use std::io::{BufReader, Cursor};
+
+    fn append(follower: &mut Follower<Cursor<Vec<u8>>>, text: &str) {
+        follower.input.get_mut().extend_from_slice(text.as_bytes());
+    }
+
+    #[test]
+    fn partial_lines() {
+        let mut follower = Follower::new(Cursor::new(Vec::new()));
+        append(&mut follower, "1 1 120\n2 1 13");
+        assert_eq!(follower.poll().unwrap(), 1);
+        assert_eq!(follower.poll().unwrap(), 0);
+
+        append(&mut follower, "0\n# A comment\n\n2 3 140\n");
+        assert_eq!(follower.poll().unwrap(), 2);
+
+        let expected = "3\n1 1 120\n2 1 130\n2 3 140\n";
+        let expected = Graph::try_from(BufReader::new(expected.as_bytes())).unwrap();
+        assert_eq!(follower.graph(), &expected);
+    }
+
+    #[test]
+    fn header() {
+        let mut follower = Follower::new(Cursor::new(Vec::new()));
+        append(&mut follower, "source: node\n1\n1 1 120\n2 1 130\n");
+        assert_eq!(follower.poll().unwrap(), 2);
+        assert_eq!(follower.graph().metadata().source(), Some("node"));
+
+        append(&mut follower, "2\n");
+        match follower.poll() {
+            Err(Error::InvalidTransaction(location, _)) => assert_eq!(location.to_string(), "5:2"),
+            res => panic!("Unexpected result: {:?}", res),
+        }
+    }
+
+    #[test]
+    fn date_times() {
+        let mut follower = Follower::new(Cursor::new(Vec::new()));
+        append(
+            &mut follower,
+            "time-unit: ms\n1 1 2024-05-01T12:00:00Z\n2 1 2024-05-01T12:00:01Z\n",
+        );
+        assert_eq!(follower.poll().unwrap(), 2);
+        assert_eq!(follower.graph().metadata().time_unit(), Some("ms"));
+
+        let timestamps: Vec<_> = follower
+            .graph()
+            .transactions()
+            .map(|t| t.timestamp())
+            .collect();
+        assert_eq!(timestamps, [1_714_564_800_000, 1_714_564_801_000]);
+
+        let mut follower = Follower::new(Cursor::new(Vec::new()));
+        append(&mut follower, "1 1 120\n2 1 2024-05-01T12:00:01Z\n");
+        assert_eq!(follower.poll().unwrap(), 2);
+        assert_eq!(follower.graph().metadata().time_unit(), Some("ms"));
+    }
+
+    #[test]
+    fn forward_references() {
+        let mut follower = Follower::new(Cursor::new(Vec::new()));
+        append(&mut follower, "1 1 120\n\n1 3 130\n");
+
+        match follower.poll() {
+            Err(e @ Error::InvalidRight(..)) => {
+                assert_eq!(e.location().unwrap().to_string(), "3:3");
+                assert_eq!(
+                    e.to_string(),
+                    "Invalid right ref to Tx:Id(3) on Tx:Id(3) max=2"
+                );
+            }
+            res => panic!("Unexpected result: {:?}", res),
+        }
+    }
+}
@@ -3,52 +3,129 @@
 //! Graph and reference structures.
 
 use crate::id::{Id, NonRootId};
-use crate::transaction::{self, Transaction};
+use crate::input::Lines;
+use crate::location::{self, Location};
+use crate::metadata::Metadata;
+use crate::names::{self, Names};
+use crate::solidify::{Solidifier, Unsolid};
+use crate::time;
+use crate::transaction::{self, Field, NamedTransaction, Transaction};
 use derive_more::Display;
 use std::collections::{HashMap as Map, HashSet as Set};
 use std::convert::TryFrom;
-use std::io::{self, BufRead, BufReader, Read};
+use std::error;
+use std::io::{self, BufRead, BufReader, Read, Write};
 use std::num::ParseIntError;
-use std::ops::Index;
+use std::ops::{Index, Range};
 use std::str::FromStr;
 
-/// Errors that can happen when dealing with graphs.
+/// Errors that can happen when dealing with graphs. Errors about the contents of an input
+/// carry the `Location` of the offending text.
 #[derive(Debug, Display)]
 pub enum Error {
-    #[display(fmt = "IO Error: {}", "_0")]
+    #[display(fmt = "IO Error")]
     IO(io::Error),
 
+    #[display(fmt = "Invalid metadata, expected `key: value`")]
+    InvalidMetadata(Location),
+
     #[display(fmt = "Missing number of transactions")]
-    MissingNumberOfTransactions,
+    MissingNumberOfTransactions(Location),
 
-    #[display(fmt = "Invalid number of transactions: {}", "_0")]
-    InvalidNumberOfTransactions(ParseIntError),
+    #[display(fmt = "Invalid number of transactions")]
+    InvalidNumberOfTransactions(Location, ParseIntError),
 
     #[display(fmt = "Too many transactions")]
-    TooManyTransactions,
+    TooManyTransactions(Location),
 
     #[display(fmt = "Too little transactions")]
-    TooLittleTransactions,
+    TooLittleTransactions(Location),
+
+    #[display(fmt = "Invalid transaction")]
+    InvalidTransaction(Location, transaction::Error),
+
+    #[display(fmt = "Invalid left ref to {} on Tx:{} max={}", "_2", "_1", "_3")]
+    InvalidLeft(Location, NonRootId, Id, usize),
+
+    #[display(fmt = "Invalid right ref to {} on Tx:{} max={}", "_2", "_1", "_3")]
+    InvalidRight(Location, NonRootId, Id, usize),
 
-    #[display(fmt = "Invalid transaction: {}", "_0")]
-    InvalidTransaction(transaction::Error),
+    #[display(fmt = "Invalid transaction identifier")]
+    InvalidName(Location, names::Error),
 
-    #[display(fmt = "Invalid left ref to {} on Tx:{} max={}", "_1", "_0", "_2")]
-    InvalidLeft(NonRootId, Id, usize),
+    #[display(fmt = "Unknown left ref to `{}` on `{}`", "_2", "_1")]
+    UnknownLeft(Location, String, String),
 
-    #[display(fmt = "Invalid right ref to {} on Tx:{} max={}", "_1", "_0", "_2")]
-    InvalidRight(NonRootId, Id, usize),
+    #[display(fmt = "Unknown right ref to `{}` on `{}`", "_2", "_1")]
+    UnknownRight(Location, String, String),
+
+    #[display(fmt = "Left ref to skipped {} on Tx:{}", "_2", "_1")]
+    SkippedLeft(Location, NonRootId, Id),
+
+    #[display(fmt = "Right ref to skipped {} on Tx:{}", "_2", "_1")]
+    SkippedRight(Location, NonRootId, Id),
+
+    #[display(fmt = "Invalid time unit for a date and time")]
+    InvalidTimeUnit(Location, time::Error),
 }
 
-impl From<io::Error> for Error {
-    fn from(e: io::Error) -> Self {
-        Error::IO(e)
+impl Error {
+    /// The location of the offending text in the input, if any.
+    pub fn location(&self) -> Option<&Location> {
+        match self {
+            Error::IO(_) => None,
+            Error::InvalidMetadata(l)
+            | Error::MissingNumberOfTransactions(l)
+            | Error::InvalidNumberOfTransactions(l, _)
+            | Error::TooManyTransactions(l)
+            | Error::TooLittleTransactions(l)
+            | Error::InvalidTransaction(l, _)
+            | Error::InvalidLeft(l, ..)
+            | Error::InvalidRight(l, ..)
+            | Error::InvalidName(l, _)
+            | Error::UnknownLeft(l, ..)
+            | Error::UnknownRight(l, ..)
+            | Error::SkippedLeft(l, ..)
+            | Error::SkippedRight(l, ..)
+            | Error::InvalidTimeUnit(l, _) => Some(l),
+        }
+    }
+
+    /// Render the error in the style of rustc, with a snippet of the offending line of the
+    /// input at `path` when the error has a location.
+    pub fn render(&self, path: &str) -> String {
+        match self.location() {
+            Some(location) => location.render(self, path),
+            None => format!("error: {}", location::chain(self)),
+        }
+    }
+}
+
+impl error::Error for Error {
+    fn source(&self) -> Option<&(dyn error::Error + 'static)> {
+        match self {
+            Error::IO(e) => Some(e),
+            Error::InvalidNumberOfTransactions(_, e) => Some(e),
+            Error::InvalidTransaction(_, e) => Some(e),
+            Error::InvalidName(_, e) => Some(e),
+            Error::InvalidTimeUnit(_, e) => Some(e),
+            Error::InvalidMetadata(_)
+            | Error::MissingNumberOfTransactions(_)
+            | Error::TooManyTransactions(_)
+            | Error::TooLittleTransactions(_)
+            | Error::InvalidLeft(..)
+            | Error::InvalidRight(..)
+            | Error::UnknownLeft(..)
+            | Error::UnknownRight(..)
+            | Error::SkippedLeft(..)
+            | Error::SkippedRight(..) => None,
+        }
     }
 }
 
-impl From<transaction::Error> for Error {
-    fn from(e: transaction::Error) -> Self {
-        Error::InvalidTransaction(e)
+impl From<io::Error> for Error {
+    fn from(e: io::Error) -> Self {
+        Error::IO(e)
     }
 }
 
@@ -75,11 +152,42 @@ impl References {
         self.count += 1;
     }
 
-    fn sources(&self) -> impl Iterator<Item = &'_ NonRootId> + '_ {
+    pub(crate) fn sources(&self) -> impl Iterator<Item = &'_ NonRootId> + '_ {
         self.inner.iter()
     }
 }
 
+/// The reverse references to a range of IDs, built apart from the `Graph` they belong to so
+/// that ranges can be built on separate threads.
+pub(crate) struct ReverseReferences(Map<Id, References>);
+
+impl ReverseReferences {
+    /// The references of `transactions` to the transactions (or the Root) with an ID in
+    /// `ids`.
+    pub(crate) fn new(transactions: &[Transaction], ids: Range<usize>) -> Self {
+        let mut reverse: Map<Id, References> = Map::with_capacity(ids.len());
+        for t in transactions {
+            for &id in [t.left(), t.right()].iter() {
+                if ids.contains(&id.into()) {
+                    reverse.entry(id).or_default().add(t.id());
+                }
+            }
+        }
+        Self(reverse)
+    }
+}
+
+/// The path from a transaction to the Root that its depth is the length of.
+#[derive(Clone, Copy, PartialEq, Eq, Debug, Default)]
+pub enum DepthPath {
+    /// The shortest path, as computed by `Graph::depth`.
+    #[default]
+    Shortest,
+
+    /// The longest path, as computed by `Graph::height`.
+    Longest,
+}
+
 /// Primary `Graph` data structure.
 #[derive(PartialEq, Eq, Debug, Default)]
 pub struct Graph {
@@ -90,6 +198,13 @@ pub struct Graph {
     /// transaction) to the set of transactions pointing to it, including the number of
     /// edges pointing to it.
     reverse: Map<Id, References>,
+
+    /// The original identifiers of the transactions, when the `Graph` was read from an
+    /// input with textual identifiers.
+    names: Option<Names>,
+
+    /// The metadata found in the header of the input the `Graph` was read from.
+    metadata: Metadata,
 }
 
 impl Graph {
@@ -97,7 +212,253 @@ impl Graph {
         Self {
             inner: Vec::with_capacity(cap),
             reverse: Map::with_capacity(cap + 1),
+            names: None,
+            metadata: Metadata::default(),
+        }
+    }
+
+    /// Read a `Graph` from an input where each line is `ID LEFT RIGHT TIMESTAMP` with
+    /// textual identifiers. Identifiers are interned into dense `Id`s in the order they are
+    /// defined, and `root` is the identifier of the Root transaction. References must point
+    /// to the Root or to transactions defined on earlier lines.
+    pub fn try_from_named<R: Read>(input: BufReader<R>, root: &str) -> Result<Self, Error> {
+        let mut lines = Lines::new(input);
+        let mut metadata = Metadata::default();
+        let n_transactions = read_header(&mut lines, &mut metadata)?;
+
+        let mut graph = Graph::with_capacity(n_transactions);
+        let mut names = Names::new(root);
+
+        // Iterate over the transaction lines.
+        for (i, line) in lines.by_ref().enumerate() {
+            let (line_no, line) = line?;
+
+            if i + 1 > n_transactions {
+                // The number of transactions read so far exceeds the expected number.
+                return Err(Error::TooManyTransactions(Location::line(line_no, &line)));
+            }
+
+            let t = NamedTransaction::try_from(line.as_str())
+                .map_err(|e| named_transaction_error(line_no, &line, e))?;
+            check_date_time(line_no, &line, 3, &mut metadata)?;
+
+            // Resolve the references before interning the transaction's own identifier so
+            // that a transaction cannot refer to itself.
+            let left = names.get(t.left).ok_or_else(|| {
+                let location = Location::token(line_no, &line, 1);
+                Error::UnknownLeft(location, t.id.into(), t.left.into())
+            })?;
+            let right = names.get(t.right).ok_or_else(|| {
+                let location = Location::token(line_no, &line, 2);
+                Error::UnknownRight(location, t.id.into(), t.right.into())
+            })?;
+
+            let id = names
+                .intern(t.id)
+                .map_err(|e| Error::InvalidName(Location::token(line_no, &line, 0), e))?;
+            graph.push(Transaction::new(id, left, right, t.timestamp));
+        }
+
+        if graph.len() < n_transactions {
+            // The number of transactions read is lower than the expected number.
+            let location = Location::line(lines.line_no() + 1, "");
+            return Err(Error::TooLittleTransactions(location));
+        }
+
+        graph.names = Some(names);
+        graph.metadata = metadata;
+        Ok(graph)
+    }
+
+    /// Read a `Graph` from an input with textual identifiers like `Graph::try_from_named`,
+    /// but accept transactions in any order. Transactions are buffered until both their
+    /// references are known, so the resulting `Graph` is in topological order. The
+    /// transactions that never became solid are returned alongside the `Graph`.
+    pub fn try_from_unordered<R: Read>(
+        input: BufReader<R>,
+        root: &str,
+    ) -> Result<(Self, Vec<Unsolid>), Error> {
+        let mut lines = Lines::new(input);
+        let mut metadata = Metadata::default();
+        let n_transactions = read_header(&mut lines, &mut metadata)?;
+        let mut solidifier = Solidifier::with_capacity(root, n_transactions);
+
+        // Iterate over the transaction lines.
+        for (i, line) in lines.by_ref().enumerate() {
+            let (line_no, line) = line?;
+
+            if i + 1 > n_transactions {
+                // The number of transactions read so far exceeds the expected number.
+                return Err(Error::TooManyTransactions(Location::line(line_no, &line)));
+            }
+
+            let t = NamedTransaction::try_from(line.as_str())
+                .map_err(|e| named_transaction_error(line_no, &line, e))?;
+            check_date_time(line_no, &line, 3, &mut metadata)?;
+            solidifier
+                .insert(t)
+                .map_err(|e| Error::InvalidName(Location::token(line_no, &line, 0), e))?;
+        }
+
+        if solidifier.n_inserted() < n_transactions {
+            // The number of transactions read is lower than the expected number.
+            let location = Location::line(lines.line_no() + 1, "");
+            return Err(Error::TooLittleTransactions(location));
         }
+
+        let (mut graph, unsolid) = solidifier.finish();
+        graph.metadata = metadata;
+        Ok((graph, unsolid))
+    }
+
+    /// Read a `Graph` like `Graph::try_from`, but skip malformed lines instead of failing
+    /// on the first one. Transactions referring to skipped transactions are skipped as
+    /// well, and the remaining transactions are renumbered to keep IDs dense. A mismatch
+    /// between the header and the actual number of transactions is tolerated. Only IO
+    /// errors are fatal, all other errors are returned alongside the `Graph` in the order
+    /// of the lines they are about.
+    pub fn try_from_lenient<R: Read>(input: BufReader<R>) -> Result<(Self, Vec<Error>), Error> {
+        let mut lines = Lines::new(input);
+        let mut metadata = Metadata::default();
+        let mut errors = Vec::new();
+
+        let n_transactions = match read_header(&mut lines, &mut metadata) {
+            Ok(n) => Some(n),
+            Err(e @ Error::IO(_)) => return Err(e),
+            Err(e) => {
+                errors.push(e);
+                None
+            }
+        };
+
+        // The parsed transactions, indexed by their position-based ID - 2, and the numbers
+        // and text of the lines they were found on, to locate errors about references once
+        // all lines are read. Skipped transactions are None.
+        let capacity = n_transactions.unwrap_or(0);
+        let mut parsed: Vec<Option<Transaction>> = Vec::with_capacity(capacity);
+        let mut line_texts: Vec<(usize, String)> = Vec::with_capacity(capacity);
+
+        // Iterate over the transaction lines.
+        for (i, line) in lines.by_ref().enumerate() {
+            let (line_no, line) = line?;
+
+            if n_transactions == Some(i) {
+                // The number of transactions read so far exceeds the expected number.
+                errors.push(Error::TooManyTransactions(Location::line(line_no, &line)));
+            }
+
+            let t = Transaction::try_from((i + 2, &line))
+                .map_err(|e| transaction_error(line_no, &line, e))
+                .and_then(|t| check_date_time(line_no, &line, 2, &mut metadata).map(|_| t));
+            match t {
+                Ok(t) => parsed.push(Some(t)),
+                Err(e) => {
+                    errors.push(e);
+                    parsed.push(None);
+                }
+            }
+
+            line_texts.push((line_no, line));
+        }
+
+        if let Some(n) = n_transactions {
+            if parsed.len() < n {
+                // The number of transactions read is lower than the expected number.
+                let location = Location::line(lines.line_no() + 1, "");
+                errors.push(Error::TooLittleTransactions(location));
+            }
+        }
+
+        // Skip transactions with references that are out of range or to skipped
+        // transactions. References can point forward, so skipping a transaction can
+        // invalidate transactions on earlier lines and this has to be repeated until no
+        // more transactions are skipped.
+        let max = parsed.len() + 1;
+        let is_skipped = |parsed: &[Option<Transaction>], id: Id| match id {
+            Id::Root => false,
+            Id::Transaction(id) => parsed[usize::from(id) - 2].is_none(),
+        };
+
+        let mut changed = true;
+        while changed {
+            changed = false;
+
+            for i in 0..parsed.len() {
+                let t = match &parsed[i] {
+                    Some(t) => t,
+                    None => continue,
+                };
+
+                let (left, right): (usize, usize) = (t.left().into(), t.right().into());
+                let (line_no, text) = &line_texts[i];
+                let left_location = || Location::token(*line_no, text, 0);
+                let right_location = || Location::token(*line_no, text, 1);
+
+                let error = if left > max {
+                    Error::InvalidLeft(left_location(), t.id(), t.left(), max)
+                } else if right > max {
+                    Error::InvalidRight(right_location(), t.id(), t.right(), max)
+                } else if is_skipped(&parsed, t.left()) {
+                    Error::SkippedLeft(left_location(), t.id(), t.left())
+                } else if is_skipped(&parsed, t.right()) {
+                    Error::SkippedRight(right_location(), t.id(), t.right())
+                } else {
+                    continue;
+                };
+
+                errors.push(error);
+                parsed[i] = None;
+                changed = true;
+            }
+        }
+
+        // Renumber the remaining transactions. The new ID of a transaction is 2 + the
+        // number of remaining transactions before it.
+        let mut new_ids = Vec::with_capacity(parsed.len());
+        let mut next = 2;
+        for t in &parsed {
+            new_ids.push(next);
+            if t.is_some() {
+                next += 1;
+            }
+        }
+
+        let renumber = |id: Id| match id {
+            Id::Root => Id::Root,
+            Id::Transaction(id) => {
+                Id::try_from(new_ids[usize::from(id) - 2]).expect("Renumbered IDs are valid")
+            }
+        };
+
+        let mut graph = Graph::with_capacity(next - 2);
+        for t in parsed.into_iter().flatten() {
+            let id = NonRootId::try_from(new_ids[usize::from(t.id()) - 2])
+                .expect("Renumbered IDs are valid");
+            graph.push(Transaction::new(
+                id,
+                renumber(t.left()),
+                renumber(t.right()),
+                t.timestamp(),
+            ));
+        }
+
+        errors.sort_by_key(|e| e.location().map_or(0, |l| l.line));
+        graph.metadata = metadata;
+        Ok((graph, errors))
+    }
+
+    /// The metadata found in the header of the input the `Graph` was read from.
+    pub fn metadata(&self) -> &Metadata {
+        &self.metadata
+    }
+
+    pub fn metadata_mut(&mut self) -> &mut Metadata {
+        &mut self.metadata
+    }
+
+    /// Attach textual identifiers to the transactions of the `Graph`.
+    pub(crate) fn set_names(&mut self, names: Names) {
+        self.names = Some(names);
     }
 
     pub fn len(&self) -> usize {
@@ -116,13 +477,55 @@ impl Graph {
         self.reverse.get(&id)
     }
 
+    /// The original identifiers of the transactions, if the `Graph` was read from an input
+    /// with textual identifiers.
+    pub fn names(&self) -> Option<&Names> {
+        self.names.as_ref()
+    }
+
+    /// The original identifier of a transaction, or its numeric ID when the `Graph` has no
+    /// textual identifiers.
+    pub fn name(&self, id: Id) -> String {
+        match self.names.as_ref().and_then(|names| names.name(id)) {
+            Some(name) => String::from(name),
+            None => usize::from(id).to_string(),
+        }
+    }
+
+    /// Write the `Graph` in the `LID RID TIMESTAMP` format that `Graph::try_from` reads.
+    pub fn write<W: Write>(&self, output: &mut W) -> io::Result<()> {
+        self.metadata.write(output)?;
+        writeln!(output, "{}", self.len())?;
+        for t in self.transactions() {
+            let left: usize = t.left().into();
+            let right: usize = t.right().into();
+            writeln!(output, "{} {} {}", left, right, t.timestamp())?;
+        }
+        Ok(())
+    }
+
+    /// Write the `Graph` in the `ID LEFT RIGHT TIMESTAMP` format that
+    /// `Graph::try_from_named` reads, using the original identifiers when available.
+    pub fn write_named<W: Write>(&self, output: &mut W) -> io::Result<()> {
+        self.metadata.write(output)?;
+        writeln!(output, "{}", self.len())?;
+        for t in self.transactions() {
+            writeln!(
+                output,
+                "{} {} {} {}",
+                self.name(t.id().into()),
+                self.name(t.left()),
+                self.name(t.right()),
+                t.timestamp()
+            )?;
+        }
+        Ok(())
+    }
+
     pub fn push(&mut self, transaction: Transaction) {
         // Insert a new entry for incoming references to the left reference of the
         // transaction.
-        let left_references = self
-            .reverse
-            .entry(transaction.left())
-            .or_insert_with(References::default);
+        let left_references = self.reverse.entry(transaction.left()).or_default();
 
         // Insert the new source transaction for the incoming reference and increment the
         // number of incoming references.
@@ -130,10 +533,7 @@ impl Graph {
 
         // Insert a new entry for incoming references to the right reference of the
         // transaction.
-        let right_references = self
-            .reverse
-            .entry(transaction.right())
-            .or_insert_with(References::default);
+        let right_references = self.reverse.entry(transaction.right()).or_default();
 
         // Insert the new source transaction for the incoming reference and increment the
         // number of incoming references.
@@ -142,6 +542,30 @@ impl Graph {
         self.inner.push(transaction);
     }
 
+    /// Build a `Graph` out of its transactions, numbered in order, and of the reverse
+    /// references to disjoint ranges of IDs that cover them, the same way as pushing the
+    /// transactions one by one would.
+    pub(crate) fn from_parts(
+        transactions: Vec<Transaction>,
+        reverse: Vec<ReverseReferences>,
+    ) -> Self {
+        let mut parts = reverse.into_iter().map(|part| part.0);
+
+        // Take over the reverse references of the first range rather than moving them.
+        let mut reverse = parts.next().unwrap_or_default();
+        reverse.reserve((transactions.len() + 1).saturating_sub(reverse.len()));
+        for part in parts {
+            reverse.extend(part);
+        }
+
+        Self {
+            inner: transactions,
+            reverse,
+            names: None,
+            metadata: Metadata::default(),
+        }
+    }
+
     /// Check whether the `Graph` is connected and acyclic.
     pub fn is_connected_acyclic(&self) -> Option<bool> {
         fn helper(graph: &Graph, vertex: Id, mut history: Set<Id>, visited: &mut Set<Id>) -> bool {
@@ -244,6 +668,38 @@ impl Graph {
         cache.insert(id, depth);
         depth
     }
+
+    /// The length of the longest path between a transaction and the Root, computed like
+    /// `Graph::depth` but keeping the longer path of the two references. Assumes the graph
+    /// is acyclic.
+    pub fn height(&self, id: NonRootId, cache: &mut Map<NonRootId, usize>) -> usize {
+        if let Some(height) = cache.get(&id) {
+            return *height;
+        }
+
+        let transaction = &self[id];
+        let mut height = |reference| match reference {
+            Id::Transaction(reference) => self.height(reference, cache) + 1,
+            Id::Root => 1,
+        };
+        let height = height(transaction.left()).max(height(transaction.right()));
+
+        cache.insert(id, height);
+        height
+    }
+
+    /// The length of the shortest or the longest path between a transaction and the Root.
+    pub fn depth_along(
+        &self,
+        id: NonRootId,
+        path: DepthPath,
+        cache: &mut Map<NonRootId, usize>,
+    ) -> usize {
+        match path {
+            DepthPath::Shortest => self.depth(id, cache),
+            DepthPath::Longest => self.height(id, cache),
+        }
+    }
 }
 
 impl Index<NonRootId> for Graph {
@@ -255,50 +711,125 @@ impl Index<NonRootId> for Graph {
     }
 }
 
-impl<R: Read> TryFrom<BufReader<R>> for Graph {
-    type Error = Error;
+/// Read the header of an input: the optional `key: value` metadata lines into `metadata`,
+/// followed by the expected number of transactions.
+fn read_header<B: BufRead>(lines: &mut Lines<B>, metadata: &mut Metadata) -> Result<usize, Error> {
+    for line in lines.by_ref() {
+        let (line_no, line) = line?;
 
-    fn try_from(input: BufReader<R>) -> Result<Self, Self::Error> {
-        let mut iter = input.lines();
+        if let Some(n_transactions) = read_header_line(line_no, &line, metadata)? {
+            return Ok(n_transactions);
+        }
+    }
 
-        // Read the expected number of transactions.
-        let n_transactions = match iter.next() {
-            Some(n) => n?,
-            None => return Err(Error::MissingNumberOfTransactions),
-        };
+    let location = Location::line(lines.line_no() + 1, "");
+    Err(Error::MissingNumberOfTransactions(location))
+}
 
-        let n_transactions = match usize::from_str(&n_transactions) {
-            Ok(n) => n,
-            Err(e) => return Err(Error::InvalidNumberOfTransactions(e)),
-        };
+/// Read a line of the header of an input, returning the expected number of transactions
+/// when the line is not metadata.
+pub(crate) fn read_header_line(
+    line_no: usize,
+    line: &str,
+    metadata: &mut Metadata,
+) -> Result<Option<usize>, Error> {
+    match Metadata::split_line(line) {
+        Some(("", _)) => Err(Error::InvalidMetadata(Location::line(line_no, line))),
+        Some((key, value)) => {
+            metadata.insert(key, value);
+            Ok(None)
+        }
+        None => usize::from_str(line.trim())
+            .map(Some)
+            .map_err(|e| Error::InvalidNumberOfTransactions(Location::line(line_no, line), e)),
+    }
+}
+
+/// Check the timestamp of a transaction, the `index`-th token of its line, with
+/// `time::declare_date_times` when it is a date and time.
+pub(crate) fn check_date_time(
+    line_no: usize,
+    line: &str,
+    index: usize,
+    metadata: &mut Metadata,
+) -> Result<(), Error> {
+    // Dates and times are the only timestamps with colons, and once `ms` is declared
+    // there is nothing left to check.
+    if !line.contains(':') || metadata.time_unit() == Some("ms") {
+        return Ok(());
+    }
+
+    match line.split_ascii_whitespace().nth(index) {
+        Some(timestamp) if time::is_date_time(timestamp) => time::declare_date_times(metadata)
+            .map_err(|e| Error::InvalidTimeUnit(Location::token(line_no, line, index), e)),
+        _ => Ok(()),
+    }
+}
+
+/// Locate an error in a `LID RID TIMESTAMP` line.
+pub(crate) fn transaction_error(line_no: usize, line: &str, e: transaction::Error) -> Error {
+    let location = match e.field() {
+        Field::Id => Location::line(line_no, line),
+        Field::Left => Location::token(line_no, line, 0),
+        Field::Right => Location::token(line_no, line, 1),
+        Field::Timestamp => Location::token(line_no, line, 2),
+    };
+
+    Error::InvalidTransaction(location, e)
+}
+
+/// Locate an error in an `ID LEFT RIGHT TIMESTAMP` line.
+fn named_transaction_error(line_no: usize, line: &str, e: transaction::Error) -> Error {
+    let token = match e.field() {
+        Field::Id => 0,
+        Field::Left => 1,
+        Field::Right => 2,
+        Field::Timestamp => 3,
+    };
 
+    Error::InvalidTransaction(Location::token(line_no, line, token), e)
+}
+
+impl<R: Read> TryFrom<BufReader<R>> for Graph {
+    type Error = Error;
+
+    fn try_from(input: BufReader<R>) -> Result<Self, Self::Error> {
+        let mut lines = Lines::new(input);
+        let mut metadata = Metadata::default();
+        let n_transactions = read_header(&mut lines, &mut metadata)?;
         let mut graph = Graph::with_capacity(n_transactions);
 
-        // Iterate over input lines.
-        for (i, line) in iter.enumerate() {
+        // Iterate over the transaction lines.
+        for (i, line) in lines.by_ref().enumerate() {
+            let (line_no, line) = line?;
+
             if i + 1 > n_transactions {
                 // The number of transactions read so far exceeds the expected number.
-                return Err(Error::TooManyTransactions);
+                return Err(Error::TooManyTransactions(Location::line(line_no, &line)));
             }
 
             // Current transaction's ID.
             let id = i + 2;
 
             // Parse the transaction.
-            let t = Transaction::try_from((id, &line?))?;
+            let t = Transaction::try_from((id, &line))
+                .map_err(|e| transaction_error(line_no, &line, e))?;
+            check_date_time(line_no, &line, 2, &mut metadata)?;
 
             let max = n_transactions + 1;
 
             // Check the transaction's left reference.
             let left: usize = t.left().into();
             if left > max {
-                return Err(Error::InvalidLeft(t.id(), t.left(), max));
+                let location = Location::token(line_no, &line, 0);
+                return Err(Error::InvalidLeft(location, t.id(), t.left(), max));
             }
 
             // Check the transaction's right reference.
             let right: usize = t.right().into();
             if right > max {
-                return Err(Error::InvalidRight(t.id(), t.right(), max));
+                let location = Location::token(line_no, &line, 1);
+                return Err(Error::InvalidRight(location, t.id(), t.right(), max));
             }
 
             graph.push(t);
@@ -306,13 +837,21 @@ impl<R: Read> TryFrom<BufReader<R>> for Graph {
 
         if graph.len() < n_transactions {
             // The number of transactions read is lower than the expected number.
-            return Err(Error::TooLittleTransactions);
+            let location = Location::line(lines.line_no() + 1, "");
+            return Err(Error::TooLittleTransactions(location));
         }
 
+        graph.metadata = metadata;
         Ok(graph)
     }
 }
 
+/// Read a graph from the text of an input, for the tests of the other modules.
+#[cfg(test)]
+pub(crate) fn test_graph(input: &str) -> Graph {
+    Graph::try_from(BufReader::new(input.as_bytes())).unwrap()
+}
+
 #[cfg(test)]
 mod graph_tests {
     use super::{Error, Graph};
@@ -417,20 +956,232 @@ mod graph_tests {
         assert_eq!(res, graph());
     }
 
+    #[test]
+    fn parse_metadata() {
+        let input = concat!(
+            "# Fixture for the graph tests\n",
+            "generator: hand\n",
+            "time-unit: ms\n",
+            "\n",
+            "2 # Transactions\n",
+            "1 1 120\n",
+            "# The second transaction\n",
+            "2 1 130 # refers to the first one\n",
+        );
+        let res = Graph::try_from(BufReader::new(input.as_bytes())).unwrap();
+        assert_eq!(
+            res.transactions().collect::<Vec<_>>(),
+            graph().transactions().collect::<Vec<_>>()
+        );
+        assert_eq!(res.metadata().generator(), Some("hand"));
+        assert_eq!(res.metadata().time_unit(), Some("ms"));
+
+        let mut output = Vec::new();
+        res.write(&mut output).unwrap();
+        assert_eq!(
+            output,
+            b"generator: hand\ntime-unit: ms\n2\n1 1 120\n2 1 130\n"
+        );
+
+        let input = String::from("generator: hand\n: ms\n2\n1 1 120\n2 1 130");
+        match Graph::try_from(BufReader::new(input.as_bytes())) {
+            Err(Error::InvalidMetadata(location)) => assert_eq!(location.line, 2),
+            Err(e) => panic!("Unexpected error type: {}", e),
+            Ok(_) => panic!("Unexpected success"),
+        }
+
+        let input = String::from("3\n1 1 120\n\n2 1 130\n# The end\n");
+        match Graph::try_from(BufReader::new(input.as_bytes())) {
+            Err(Error::TooLittleTransactions(location)) => assert_eq!(location.line, 6),
+            Err(e) => panic!("Unexpected error type: {}", e),
+            Ok(_) => panic!("Unexpected success"),
+        }
+    }
+
+    #[test]
+    fn parse_date_times() {
+        let input = "2\n1 1 2024-05-01T12:00:00Z\n2 1 2024-05-01T12:00:03Z\n";
+        let res = Graph::try_from(BufReader::new(input.as_bytes())).unwrap();
+        assert_eq!(res.metadata().time_unit(), Some("ms"));
+
+        let input = "time-unit: s\n2\n1 1 120\n2 1 2024-05-01T12:00:03Z\n";
+        match Graph::try_from(BufReader::new(input.as_bytes())) {
+            Err(e @ Error::InvalidTimeUnit(..)) => {
+                assert_eq!(e.location().unwrap().to_string(), "4:5");
+            }
+            Err(e) => panic!("Unexpected error type: {}", e),
+            Ok(_) => panic!("Unexpected success"),
+        }
+    }
+
     #[test]
     fn parse_fail() {
         let input = String::from("\n1 1 120\n2 1 130");
         let input = input.as_bytes();
         let res = Graph::try_from(BufReader::new(input));
-        let parse_err = "".parse::<usize>().err().unwrap();
+        let parse_err = "1 1 120".parse::<usize>().err().unwrap();
+
+        match res {
+            Err(Error::InvalidNumberOfTransactions(_, e)) => assert_eq!(e, parse_err),
+            Err(e) => panic!("Unexpected error type: {}", e),
+            Ok(_) => panic!("Unexpected success"),
+        }
+    }
+
+    #[test]
+    fn parse_fail_location() {
+        let input = String::from("3\n1 1 120\n2 abc 130\n2 2 140");
+        let input = input.as_bytes();
+        let res = Graph::try_from(BufReader::new(input)).err().unwrap();
+        let location = res.location().unwrap();
+        assert_eq!((location.line, location.columns.clone()), (3, 2..5));
+        assert_eq!(location.offending(), "abc");
+
+        let expected = concat!(
+            "error: Invalid transaction: Invalid right reference: invalid digit found in string\n",
+            " --> input.in:3:3\n",
+            "  |\n",
+            "3 | 2 abc 130\n",
+            "  |   ^^^",
+        );
+        assert_eq!(res.render("input.in"), expected);
+
+        let input = String::from("2\n1 1 120\n2 1 130\n2 2 140");
+        let input = input.as_bytes();
+        match Graph::try_from(BufReader::new(input)) {
+            Err(Error::TooManyTransactions(location)) => assert_eq!(location.line, 4),
+            Err(e) => panic!("Unexpected error type: {}", e),
+            Ok(_) => panic!("Unexpected success"),
+        }
+
+        let input = String::from("2\n1 1 120\n9 2 130");
+        let input = input.as_bytes();
+        match Graph::try_from(BufReader::new(input)) {
+            Err(Error::InvalidLeft(location, ..)) => assert_eq!(location.offending(), "9"),
+            Err(e) => panic!("Unexpected error type: {}", e),
+            Ok(_) => panic!("Unexpected success"),
+        }
+    }
+
+    #[test]
+    fn parse_lenient() {
+        // Line 3 is malformed, line 5 refers to it and line 4 refers forward to line 5.
+        let input = String::from("5\n1 1 120\n2 abc 130\n2 5 140\n1 3 150\n2 2 160\n1 9 170");
+        let input = input.as_bytes();
+        let (res, errors) = Graph::try_from_lenient(BufReader::new(input)).unwrap();
+
+        let lines: Vec<usize> = errors.iter().map(|e| e.location().unwrap().line).collect();
+        assert_eq!(lines, vec![3, 4, 5, 7, 7]);
+
+        assert!(matches!(errors[0], Error::InvalidTransaction(..)));
+        assert!(matches!(errors[1], Error::SkippedRight(..)));
+        assert!(matches!(errors[2], Error::SkippedRight(..)));
+        assert!(matches!(errors[3], Error::TooManyTransactions(_)));
+        assert!(matches!(errors[4], Error::InvalidRight(..)));
+
+        let mut expected = Graph::default();
+        expected.push(Transaction::new(
+            NonRootId::try_from(2).unwrap(),
+            Id::Root,
+            Id::Root,
+            120,
+        ));
+        expected.push(Transaction::new(
+            NonRootId::try_from(3).unwrap(),
+            Id::try_from(2).unwrap(),
+            Id::try_from(2).unwrap(),
+            160,
+        ));
+        assert_eq!(res, expected);
+        // Errors about references are located in the line as it was written.
+        let input = String::from("1\n  1    9  170 # Too far\n");
+        let (_, errors) = Graph::try_from_lenient(BufReader::new(input.as_bytes())).unwrap();
+        let location = errors[0].location().unwrap();
+        assert_eq!(location.to_string(), "2:8");
+        assert_eq!(location.offending(), "9");
+        assert_eq!(location.text, "  1    9  170 ");
+    }
+
+    #[test]
+    fn parse_lenient_header() {
+        let input = String::from("x\n1 1 120\n2 1 130");
+        let input = input.as_bytes();
+        let (res, errors) = Graph::try_from_lenient(BufReader::new(input)).unwrap();
+        assert_eq!(res, graph());
+        assert_eq!(errors.len(), 1);
+
+        let input = String::from("3\n1 1 120\n2 1 130");
+        let input = input.as_bytes();
+        let (res, errors) = Graph::try_from_lenient(BufReader::new(input)).unwrap();
+        assert_eq!(res, graph());
+        match &errors[..] {
+            [Error::TooLittleTransactions(location)] => assert_eq!(location.line, 4),
+            _ => panic!("Unexpected errors: {:?}", errors),
+        }
+    }
+
+    #[test]
+    fn parse_named_success() {
+        let input = String::from("2\nc7 genesis genesis 120\n9b c7 genesis 130");
+        let input = input.as_bytes();
+        let res = Graph::try_from_named(BufReader::new(input), "genesis").unwrap();
+        assert_eq!(res.len(), 2);
+        assert_eq!(
+            res.transactions().collect::<Vec<_>>(),
+            graph().transactions().collect::<Vec<_>>()
+        );
+        assert_eq!(res.name(Id::Root), "genesis");
+        assert_eq!(res.name(Id::try_from(3).unwrap()), "9b");
+    }
+
+    #[test]
+    fn parse_named_fail() {
+        let input = String::from("2\nc7 genesis 9b 120\n9b c7 genesis 130");
+        let input = input.as_bytes();
+        let res = Graph::try_from_named(BufReader::new(input), "genesis");
 
         match res {
-            Err(Error::InvalidNumberOfTransactions(e)) => assert_eq!(e, parse_err),
+            Err(Error::UnknownRight(location, id, right)) => {
+                assert_eq!((id.as_str(), right.as_str()), ("c7", "9b"));
+                assert_eq!(location.to_string(), "2:12");
+                assert_eq!(location.offending(), "9b");
+            }
             Err(e) => panic!("Unexpected error type: {}", e),
             Ok(_) => panic!("Unexpected success"),
         }
     }
 
+    #[test]
+    fn parse_unordered() {
+        let input = String::from("3\n9b c7 genesis 130\nc7 genesis genesis 120\nxx yy c7 140");
+        let input = input.as_bytes();
+        let (res, unsolid) = Graph::try_from_unordered(BufReader::new(input), "genesis").unwrap();
+        assert_eq!(
+            res.transactions().collect::<Vec<_>>(),
+            graph().transactions().collect::<Vec<_>>()
+        );
+        assert_eq!(unsolid.len(), 1);
+        assert_eq!(unsolid[0].id, "xx");
+    }
+
+    #[test]
+    fn write() {
+        let mut output = Vec::new();
+        graph().write(&mut output).unwrap();
+        assert_eq!(output, b"2\n1 1 120\n2 1 130\n");
+
+        let mut output = Vec::new();
+        graph().write_named(&mut output).unwrap();
+        assert_eq!(output, b"2\n2 1 1 120\n3 2 1 130\n");
+
+        let input = String::from("2\nc7 genesis genesis 120\n9b c7 genesis 130\n");
+        let input = input.as_bytes();
+        let named = Graph::try_from_named(BufReader::new(input), "genesis").unwrap();
+        let mut output = Vec::new();
+        named.write_named(&mut output).unwrap();
+        assert_eq!(output, input);
+    }
+
     #[test]
     fn bipartite() {
         assert!(!graph().is_bipartite());
@@ -1,24 +1,413 @@
 #![warn(clippy::all)]
 
+use conv::errors::PosOverflow;
 use conv::ValueFrom;
+use graphstats::arrivals::Arrivals;
+use graphstats::diff::{Diff, StatChange};
+use graphstats::extract::{Policy, Selection};
+use graphstats::follow::Follower;
 use graphstats::graph::Graph;
-use graphstats::stats::{self, Stat};
+use graphstats::incremental;
+use graphstats::metadata::Metadata;
+use graphstats::parents::ParentDistances;
+use graphstats::reduction::Redundancy;
+use graphstats::stats::{self, Stat, StatResult};
+use graphstats::time::{self, TimeUnit, Timestamp};
+use graphstats::trunk::TrunkBranch;
+use graphstats::width::Width;
+use graphstats::window::Window;
 use log::{error, info, warn};
-use std::convert::TryFrom;
+use std::fmt::Display;
 use std::fs::File;
-use std::io::BufReader;
+use std::io::{self, BufReader, BufWriter, Write};
+use std::num::NonZeroU64;
+use std::ops::Range;
 use std::process;
+use std::str::FromStr;
+use std::thread;
+use std::time::{Duration, Instant};
+use structopt::clap::{self, ArgGroup, ErrorKind};
 use structopt::StructOpt;
 
 #[derive(StructOpt)]
 struct Opt {
-    #[structopt(name = "input-file", help = "Input file")]
-    input: String,
+    #[structopt(name = "input-file", help = "Input file, unless a subcommand is given")]
+    input: Option<String>,
 
     #[structopt(short = "-d", help = "Disable (slow) graph validation")]
     no_validation: bool,
+
+    #[structopt(
+        short = "-n",
+        long = "named",
+        help = "Read `ID LEFT RIGHT TIMESTAMP` lines with textual identifiers"
+    )]
+    named: bool,
+
+    #[structopt(
+        long = "root",
+        default_value = "1",
+        help = "Identifier of the root transaction with --named"
+    )]
+    root: String,
+
+    #[structopt(
+        long = "unordered",
+        requires = "named",
+        help = "Accept transactions in any order with --named"
+    )]
+    unordered: bool,
+
+    #[structopt(
+        long = "lenient",
+        conflicts_with = "named",
+        help = "Skip malformed lines and report them instead of failing"
+    )]
+    lenient: bool,
+
+    #[structopt(
+        short = "-j",
+        long = "jobs",
+        conflicts_with_all = &["named", "lenient"],
+        help = "Parse the input on this many threads"
+    )]
+    jobs: Option<usize>,
+
+    #[structopt(
+        long = "follow",
+        conflicts_with_all = &["named", "lenient", "jobs"],
+        help = "Keep reading transactions appended to the input and reprint the statistics"
+    )]
+    follow: bool,
+
+    #[structopt(
+        long = "interval",
+        requires = "follow",
+        help = "Reprint the statistics every this many seconds with --follow [default: 1]"
+    )]
+    interval: Option<f64>,
+
+    #[structopt(
+        long = "every",
+        requires = "follow",
+        help = "Reprint the statistics every this many new transactions with --follow"
+    )]
+    every: Option<usize>,
+
+    #[structopt(
+        long = "time-unit",
+        value_name = "UNIT",
+        help = "Unit of the timestamps, ticks, ms or s [default: the time-unit of the header]"
+    )]
+    time_unit: Option<TimeUnit>,
+
+    #[structopt(
+        long = "window",
+        value_name = "SIZE",
+        conflicts_with = "follow",
+        help = "Print the statistics of every window of this many timestamp units"
+    )]
+    window: Option<NonZeroU64>,
+
+    #[structopt(
+        long = "arrivals",
+        conflicts_with = "window",
+        help = "Also print the distributions of inter-arrival gaps and of transactions per \
+                timestamp, the burstiness and how well the arrivals fit a Poisson process"
+    )]
+    arrivals: bool,
+
+    #[structopt(
+        long = "heights",
+        conflicts_with_all = &["window", "follow"],
+        help = "Also print the lengths of the longest paths to the root and how much longer \
+                they are than the shortest ones"
+    )]
+    heights: bool,
+
+    #[structopt(
+        long = "trunk-branch",
+        conflicts_with_all = &["window", "follow"],
+        help = "Also print statistics that tell left references, the trunks, apart from \
+                right references, the branches"
+    )]
+    trunk_branch: bool,
+
+    #[structopt(
+        long = "parents",
+        conflicts_with_all = &["window", "follow"],
+        help = "Also print how far back in IDs, time and depth transactions refer to"
+    )]
+    parents: bool,
+
+    #[structopt(
+        long = "redundancy",
+        conflicts_with_all = &["window", "follow"],
+        help = "Also print how many references point to transactions the other reference \
+                already refers to, directly or not"
+    )]
+    redundancy: bool,
+
+    #[structopt(subcommand)]
+    command: Option<Command>,
+}
+
+#[derive(StructOpt)]
+enum Command {
+    #[structopt(about = "Print the transactions whose parent selection is the most unusual")]
+    Anomalies {
+        #[structopt(name = "input-file", help = "Input file")]
+        input: String,
+
+        #[structopt(
+            short = "-n",
+            long = "top",
+            default_value = "10",
+            help = "Number of transactions to print"
+        )]
+        top: usize,
+    },
+
+    #[structopt(about = "Print a hash of the graph that does not depend on its numbering")]
+    Canonical {
+        #[structopt(name = "input-file", help = "Input file")]
+        input: String,
+
+        #[structopt(
+            short = "-o",
+            long = "output",
+            help = "Write the canonically relabeled graph to this file"
+        )]
+        output: Option<String>,
+    },
+
+    #[structopt(about = "Compare an old and a new snapshot of the same ledger")]
+    Diff {
+        #[structopt(name = "old-file", help = "Old snapshot")]
+        old: String,
+
+        #[structopt(name = "new-file", help = "New snapshot")]
+        new: String,
+    },
+
+    #[structopt(about = "Print the transactions whose removal disconnects others from the root")]
+    Dominators {
+        #[structopt(name = "input-file", help = "Input file")]
+        input: String,
+
+        #[structopt(
+            long = "of",
+            value_name = "ID",
+            help = "Also print the transactions every path from this transaction to the root \
+                    passes through"
+        )]
+        of: Option<usize>,
+    },
+
+    #[structopt(about = "Extract a subgraph into a valid graph file")]
+    Extract(ExtractOpt),
+
+    #[structopt(about = "Print the transactions that stopped being approved")]
+    Orphans {
+        #[structopt(name = "input-file", help = "Input file")]
+        input: String,
+
+        #[structopt(
+            long = "cutoff",
+            value_name = "TIMESTAMP",
+            help = "Print the transactions that nothing approves from this timestamp on"
+        )]
+        cutoff: TimestampArg,
+
+        #[structopt(
+            long = "window",
+            value_name = "SIZE",
+            help = "Also print the orphan rate of every window of this many timestamp units"
+        )]
+        window: Option<NonZeroU64>,
+    },
+
+    #[structopt(about = "Prune old transactions and report how the statistics shift")]
+    Prune(PruneOpt),
+
+    #[structopt(about = "Print how many transactions are mutually unreachable")]
+    Width {
+        #[structopt(name = "input-file", help = "Input file")]
+        input: String,
+
+        #[structopt(
+            long = "window",
+            value_name = "SIZE",
+            help = "Also print the width of every window of this many timestamp units"
+        )]
+        window: Option<NonZeroU64>,
+    },
+}
+
+#[derive(StructOpt)]
+struct PruneOpt {
+    #[structopt(name = "input-file", help = "Input file")]
+    input: String,
+
+    #[structopt(
+        long = "before",
+        value_name = "TIMESTAMP",
+        help = "Prune the transactions older than this timestamp"
+    )]
+    before: TimestampArg,
+
+    #[structopt(
+        long = "entry-points",
+        help = "Keep the pruned transactions that remaining ones refer to as entry points"
+    )]
+    entry_points: bool,
+
+    #[structopt(
+        short = "-o",
+        long = "output",
+        help = "Write the pruned graph to this file"
+    )]
+    output: Option<String>,
+}
+
+#[derive(StructOpt)]
+#[structopt(group = ArgGroup::with_name("selection").required(true))]
+struct ExtractOpt {
+    #[structopt(name = "input-file", help = "Input file")]
+    input: String,
+
+    #[structopt(
+        long = "timestamps",
+        value_name = "FROM..TO",
+        group = "selection",
+        parse(try_from_str = parse_timestamp_range),
+        help = "Extract the transactions with a timestamp in the range"
+    )]
+    timestamps: Option<Range<TimestampArg>>,
+
+    #[structopt(
+        long = "depths",
+        value_name = "FROM..TO",
+        group = "selection",
+        parse(try_from_str = parse_range),
+        help = "Extract the transactions with a depth in the range"
+    )]
+    depths: Option<Range<usize>>,
+
+    #[structopt(
+        long = "ids",
+        value_name = "FROM..TO",
+        group = "selection",
+        parse(try_from_str = parse_range),
+        help = "Extract the transactions with an ID in the range"
+    )]
+    ids: Option<Range<usize>>,
+
+    #[structopt(
+        long = "past",
+        value_name = "ID",
+        group = "selection",
+        help = "Extract a transaction and the transactions it refers to"
+    )]
+    past: Option<usize>,
+
+    #[structopt(
+        long = "future",
+        value_name = "ID",
+        group = "selection",
+        help = "Extract a transaction and the transactions referring to it"
+    )]
+    future: Option<usize>,
+
+    #[structopt(
+        long = "policy",
+        default_value = "root",
+        possible_values = &["root", "drop"],
+        parse(try_from_str = parse_policy),
+        help = "Redirect references to transactions that are not extracted to the root, \
+                or drop the transactions with such references"
+    )]
+    policy: Policy,
+
+    #[structopt(
+        short = "-o",
+        long = "output",
+        help = "Output file, instead of the standard output"
+    )]
+    output: Option<String>,
+}
+
+/// Parse a `FROM..TO` range, where both ends are optional and `TO` is excluded.
+fn parse_range(s: &str) -> Result<Range<usize>, String> {
+    split_range(s, usize::from_str, 0..usize::MAX)
 }
 
+/// A timestamp given on the command line, as a number or as an RFC 3339 date and time.
+#[derive(Clone, Copy)]
+struct TimestampArg {
+    timestamp: Timestamp,
+    date_time: bool,
+}
+
+impl TimestampArg {
+    const MIN: Self = Self {
+        timestamp: 0,
+        date_time: false,
+    };
+    const MAX: Self = Self {
+        timestamp: Timestamp::MAX,
+        date_time: false,
+    };
+}
+
+impl FromStr for TimestampArg {
+    type Err = time::Error;
+
+    fn from_str(s: &str) -> Result<Self, Self::Err> {
+        Ok(Self {
+            timestamp: time::parse_timestamp(s)?,
+            date_time: time::is_date_time(s),
+        })
+    }
+}
+
+/// Parse a `FROM..TO` range of timestamps, given as numbers or as RFC 3339 dates and times.
+fn parse_timestamp_range(s: &str) -> Result<Range<TimestampArg>, String> {
+    split_range(
+        s,
+        TimestampArg::from_str,
+        TimestampArg::MIN..TimestampArg::MAX,
+    )
+}
+
+/// Split a `FROM..TO` range and parse its ends, which default to the ends of `all`.
+fn split_range<T, E: Display>(
+    s: &str,
+    parse: fn(&str) -> Result<T, E>,
+    all: Range<T>,
+) -> Result<Range<T>, String> {
+    let (from, to) = s
+        .split_once("..")
+        .ok_or_else(|| format!("Expected FROM..TO, found `{}`", s))?;
+    let parse = |bound: &str, default| match bound {
+        "" => Ok(default),
+        bound => parse(bound).map_err(|e| format!("{}: `{}`", e, bound)),
+    };
+
+    Ok(parse(from, all.start)?..parse(to, all.end)?)
+}
+
+fn parse_policy(s: &str) -> Result<Policy, String> {
+    match s {
+        "root" => Ok(Policy::Root),
+        "drop" => Ok(Policy::Drop),
+        _ => Err(format!("Unknown policy `{}`", s)),
+    }
+}
+
+/// How often a followed input is checked for new transactions.
+const POLL_INTERVAL: Duration = Duration::from_millis(100);
+
 // Main's return type feature could have been used, but unfortunately it means that the
 // Debug implementation of errors is going to be displayed to users and this is not
 // ideal. Instead, we manually handle errors in main() and use process::exit().
@@ -26,72 +415,537 @@ fn main() {
     env_logger::init();
 
     let opts = Opt::from_args();
-    info!("Input file = {}", opts.input);
 
-    let input_file = File::open(&opts.input).unwrap_or_else(|e| {
-        error!("Error opening file `{}`: {}", opts.input, e);
-        process::exit(1);
+    match &opts.command {
+        Some(Command::Anomalies { input, top }) => anomalies(input, *top),
+        Some(Command::Canonical { input, output }) => canonical(input, output.as_deref()),
+        Some(Command::Diff { old, new }) => diff(old, new),
+        Some(Command::Dominators { input, of }) => dominators(input, *of),
+        Some(Command::Extract(extract_opts)) => extract(extract_opts),
+        Some(Command::Orphans {
+            input,
+            cutoff,
+            window,
+        }) => orphans(input, *cutoff, *window),
+        Some(Command::Prune(prune_opts)) => prune(prune_opts),
+        Some(Command::Width { input, window }) => width(input, *window),
+        None => {}
+    }
+
+    let input = opts.input.as_deref().unwrap_or_else(|| {
+        let message = "The input file is required without a subcommand";
+        clap::Error::with_description(message, ErrorKind::MissingRequiredArgument).exit()
     });
+    info!("Input file = {}", input);
+
+    let input_file = open(input);
+
+    if opts.follow {
+        follow(input_file, input, &opts);
+    }
+
+    let start = Instant::now();
+    let graph = if opts.unordered {
+        Graph::try_from_unordered(BufReader::new(input_file), &opts.root).map(|(graph, unsolid)| {
+            for t in &unsolid {
+                let names = graph.names().expect("Unordered graphs have names");
+                warn!(
+                    "Transaction {} never became solid, missing {}",
+                    t,
+                    t.missing(names)
+                );
+            }
+            graph
+        })
+    } else if opts.lenient {
+        Graph::try_from_lenient(BufReader::new(input_file)).map(|(graph, errors)| {
+            for e in &errors {
+                warn!("Skipping a line of `{}`:\n{}", input, e.render(input));
+            }
+            if !errors.is_empty() {
+                warn!(
+                    "{} errors in `{}`, computing statistics on {} valid transactions",
+                    errors.len(),
+                    input,
+                    graph.len()
+                );
+            }
+            graph
+        })
+    } else if opts.named {
+        Graph::try_from_named(BufReader::new(input_file), &opts.root)
+    } else {
+        Graph::try_from_file(input_file, opts.jobs.unwrap_or(1))
+    };
 
-    let graph = Graph::try_from(BufReader::new(input_file)).unwrap_or_else(|e| {
-        error!("Error reading graph from `{}`: {}", opts.input, e);
+    let mut graph = graph.unwrap_or_else(|e| {
+        error!("Error reading graph from `{}`:\n{}", input, e.render(input));
         process::exit(2);
     });
 
-    info!("Loaded {} transactions", graph.len());
+    info!(
+        "Loaded {} transactions in {:.2?}",
+        graph.len(),
+        start.elapsed()
+    );
+    for (key, value) in graph.metadata().iter() {
+        info!("Metadata {} = {}", key, value);
+    }
     info!("Graph:");
     for transaction in graph.transactions() {
-        info!("  {}", transaction);
+        if graph.names().is_some() {
+            info!(
+                "  {} <{}, {}, {}>",
+                graph.name(transaction.id().into()),
+                graph.name(transaction.left()),
+                graph.name(transaction.right()),
+                transaction.timestamp()
+            );
+        } else {
+            info!("  {}", transaction);
+        }
     }
 
     if !opts.no_validation {
-        match graph.is_connected_acyclic() {
-            Some(true) => info!("Graph is connected and acyclic"),
-            Some(false) => {
-                error!("Graph is connected but cyclic, this is not supported");
-                process::exit(3);
+        validate(&graph);
+    }
+
+    // The time unit given on the command line takes precedence over the header.
+    match opts.time_unit {
+        Some(unit) => graph
+            .metadata_mut()
+            .insert(Metadata::TIME_UNIT, &unit.to_string()),
+        None => {
+            if let Err(e) = TimeUnit::from_metadata(graph.metadata()) {
+                error!("{}", e);
+                process::exit(1);
+            }
+        }
+    }
+
+    match opts.window {
+        Some(size) => print_windows(graph.windows(size)),
+        None => {
+            let mut stats = stats::defaults(&graph);
+            if opts.arrivals {
+                stats.push(Box::new(Arrivals::new(&graph)));
+            }
+            if opts.heights {
+                stats.push(Box::new(stats::Heights::new(&graph)));
             }
-            None => {
-                error!("Graph is unconnected, this is not supported");
-                process::exit(4);
+            if opts.trunk_branch {
+                stats.push(Box::new(TrunkBranch::new(&graph)));
             }
+            if opts.parents {
+                stats.push(Box::new(ParentDistances::new(&graph)));
+            }
+            if opts.redundancy {
+                stats.push(Box::new(Redundancy::new(&graph)));
+            }
+            print_results(stats::accumulate(&graph, stats));
         }
+    }
+}
 
-        if !graph.is_bipartite() {
-            warn!("Graph is not bipartite, this should not be a problem");
-        } else {
-            info!("Graph is bipartite");
+/// Follow the input, reprinting the statistics when enough time has passed or enough
+/// transactions were appended since they were last printed.
+fn follow(input_file: File, input: &str, opts: &Opt) -> ! {
+    let interval = match (opts.interval, opts.every) {
+        (Some(interval), _) => Some(interval),
+        (None, Some(_)) => None,
+        (None, None) => Some(1.0),
+    };
+    let interval = interval.map(|interval| {
+        Duration::try_from_secs_f64(interval).unwrap_or_else(|e| {
+            error!("Invalid interval {}: {}", interval, e);
+            process::exit(1);
+        })
+    });
+
+    // The statistics are kept up to date as transactions are read instead of being
+    // recomputed from scratch for each report.
+    let mut follower = Follower::new(input_file);
+    follower.subscribe(Box::new(incremental::Depths::default()));
+    follower.subscribe(Box::new(incremental::InReferences::default()));
+    follower.subscribe(Box::new(match opts.time_unit {
+        Some(unit) => stats::Throughput::with_unit(unit),
+        None => stats::Throughput::default(),
+    }));
+    follower.subscribe(Box::new(stats::Timestamps::default()));
+    if opts.arrivals {
+        follower.subscribe(Box::new(match opts.time_unit {
+            Some(unit) => Arrivals::with_unit(unit),
+            None => Arrivals::default(),
+        }));
+    }
+    let mut n_new = 0;
+    let mut printed = Instant::now();
+    let mut first = true;
+
+    loop {
+        match follower.poll() {
+            Ok(n) => n_new += n,
+            Err(e) => {
+                error!("Error reading graph from `{}`:\n{}", input, e.render(input));
+                process::exit(2);
+            }
+        }
+
+        let due = first
+            || opts.every.is_some_and(|every| n_new >= every)
+            || interval.is_some_and(|interval| printed.elapsed() >= interval);
+
+        if due && n_new > 0 {
+            let n_transactions = follower.graph().len();
+            info!(
+                "Read {} new transactions, {} in total",
+                n_new, n_transactions
+            );
+
+            if !first {
+                println!();
+            }
+            print_results(follower.observed().results());
+
+            n_new = 0;
+            printed = Instant::now();
+            first = false;
         }
+
+        thread::sleep(POLL_INTERVAL);
     }
+}
 
-    let n_transactions = match f64::value_from(graph.len()) {
-        Ok(n) => n,
+/// Exit when the graph is cyclic or unconnected, which is not supported.
+fn validate(graph: &Graph) {
+    match graph.is_connected_acyclic() {
+        Some(true) => info!("Graph is connected and acyclic"),
+        Some(false) => {
+            error!("Graph is connected but cyclic, this is not supported");
+            process::exit(3);
+        }
+        None => {
+            error!("Graph is unconnected, this is not supported");
+            process::exit(4);
+        }
+    }
+
+    if !graph.is_bipartite() {
+        warn!("Graph is not bipartite, this should not be a problem");
+    } else {
+        info!("Graph is bipartite");
+    }
+}
+
+/// Print the transactions whose parent selection is the most unusual, with the z-score of
+/// each feature.
+fn anomalies(input: &str, top: usize) -> ! {
+    let graph = load(input);
+    validate(&graph);
+
+    match graph.anomalies() {
+        Ok(anomalies) => {
+            println!("# ID\tSCORE\tSTALENESS\tLATE ROOT\tSAME PARENTS");
+            for anomaly in anomalies.iter().take(top) {
+                println!("{}", anomaly);
+            }
+        }
         Err(e) => {
-            error!("Error converting graph length to float: {}", e);
-            std::process::exit(1);
+            error!("Error calculating result: {}", e);
+            process::exit(1);
         }
+    }
+
+    process::exit(0);
+}
+
+/// Print the canonical hash of a graph and write out its canonical relabeling if requested.
+fn canonical(input: &str, output: Option<&str>) -> ! {
+    let graph = load(input);
+    let hash = graph.canonical_hash().unwrap_or_else(|| {
+        error!("Graph is cyclic, it has no canonical relabeling");
+        process::exit(3);
+    });
+
+    println!("> CANONICAL HASH: {:016x}", hash);
+
+    if let Some(output) = output {
+        let canonical = graph.canonical().expect("Graph is acyclic");
+        write(&canonical, Some(output));
+    }
+
+    process::exit(0);
+}
+
+/// Compare two snapshots of the same ledger, exiting with an error when the old snapshot
+/// is not a prefix of the new one.
+fn diff(old_input: &str, new_input: &str) -> ! {
+    let old = load(old_input);
+    validate(&old);
+    let new = load(new_input);
+    validate(&new);
+    let diff = Diff::new(&old, &new);
+
+    println!("> CONFLICTING TXS: {}", diff.conflicts().len());
+    for conflict in diff.conflicts() {
+        println!(">   {}", conflict);
+    }
+
+    println!("> ADDED TXS: {}", diff.added().count());
+    for transaction in diff.added() {
+        info!("  {}", transaction);
+    }
+
+    print_changes(diff.stat_changes());
+
+    if !diff.is_prefix() {
+        error!("`{}` is not a prefix of `{}`", old_input, new_input);
+        process::exit(5);
+    }
+
+    process::exit(0);
+}
+
+/// Extract a subgraph and write it out.
+fn extract(opts: &ExtractOpt) -> ! {
+    let selection = if let Some(range) = &opts.timestamps {
+        Selection::Timestamps(range.start.timestamp..range.end.timestamp)
+    } else if let Some(range) = &opts.depths {
+        Selection::Depths(range.clone())
+    } else if let Some(range) = &opts.ids {
+        Selection::Ids(range.clone())
+    } else if let Some(id) = opts.past {
+        Selection::Past(id)
+    } else if let Some(id) = opts.future {
+        Selection::Future(id)
+    } else {
+        unreachable!("A selection is required")
     };
 
-    let mut stats: Vec<Box<dyn Stat>> = vec![
-        Box::new(stats::Depths::new(&graph)),
-        Box::new(stats::InReferences::new(&graph)),
-        Box::new(stats::TimeUnits::default()),
-        Box::new(stats::Timestamps::new(&graph)),
-    ];
+    let mut graph = load(&opts.input);
+    validate(&graph);
+    if let Some(range) = &opts.timestamps {
+        declare_date_times(&mut graph, &[range.start, range.end]);
+    }
+    let extracted = graph.extract(&selection, opts.policy);
+    info!(
+        "Extracted {} out of {} transactions",
+        extracted.len(),
+        graph.len()
+    );
 
-    for transaction in graph.transactions() {
-        for stat in &mut stats {
-            stat.accumulate(transaction);
+    write(&extracted, opts.output.as_deref());
+    process::exit(0);
+}
+
+/// Prune old transactions, reporting how the statistics shift and writing out the pruned
+/// graph if requested.
+fn prune(opts: &PruneOpt) -> ! {
+    let mut graph = load(&opts.input);
+    validate(&graph);
+    declare_date_times(&mut graph, &[opts.before]);
+    let pruned = if opts.entry_points {
+        graph.prune_to_entry_points(opts.before.timestamp)
+    } else {
+        graph.prune(opts.before.timestamp)
+    };
+
+    println!("> PRUNED TXS: {}", pruned.n_pruned);
+    if opts.entry_points {
+        println!("> ENTRY POINTS: {}", pruned.n_entry_points);
+    }
+
+    print_changes(pruned.stat_changes(&graph));
+
+    if let Some(output) = &opts.output {
+        write(&pruned.graph, Some(output));
+    }
+
+    process::exit(0);
+}
+
+/// Print the transactions dominating others, with the number of transactions each of them
+/// dominates, and the dominators of a transaction if requested.
+fn dominators(input: &str, of: Option<usize>) -> ! {
+    let graph = load(input);
+    validate(&graph);
+
+    let dominators = graph.dominators();
+    if let Some(id) = of {
+        if dominators.immediate(id).is_none() {
+            error!("Transaction {} is not in the graph", id);
+            process::exit(1);
+        }
+        let chain: Vec<String> = dominators
+            .chain(id)
+            .iter()
+            .map(ToString::to_string)
+            .collect();
+        if chain.is_empty() {
+            println!("> DOMINATORS OF {}: -", id);
+        } else {
+            println!("> DOMINATORS OF {}: {}", id, chain.join(", "));
         }
     }
 
-    for stat in stats {
-        match stat.result(n_transactions) {
-            Ok(r) => println!("{}", r),
-            Err(e) => {
-                error!("Error calculating result: {}", e);
-                std::process::exit(1);
+    let articulations = dominators.articulations();
+    println!("> ARTICULATION TRANSACTIONS: {}", articulations.len());
+    println!("# ID\tDOMINATED");
+    for articulation in articulations {
+        println!("{}", articulation);
+    }
+
+    process::exit(0);
+}
+
+/// Print the transactions issued before the cutoff that nothing approves from the cutoff
+/// on, with the last time they were approved, and the orphan rate over time if requested.
+fn orphans(input: &str, cutoff: TimestampArg, window: Option<NonZeroU64>) -> ! {
+    let mut graph = load(input);
+    validate(&graph);
+    declare_date_times(&mut graph, &[cutoff]);
+    let cutoff = cutoff.timestamp;
+
+    let orphans = graph.orphans(cutoff);
+    let judged = graph
+        .transactions()
+        .filter(|t| t.timestamp() < cutoff)
+        .count();
+    let rate = f64::value_from(orphans.len())
+        .and_then(|n_orphans| Ok(n_orphans / f64::value_from(judged)?));
+    println!("> ORPHANS: {}", orphans.len());
+    println!("> ORPHAN RATE: {:.2}", calculated(rate));
+
+    if let Some(size) = window {
+        println!("# START\tTXS\tORPHANS\tRATE");
+        for rate in calculated(graph.orphan_rates(cutoff, size)) {
+            println!("{}", rate);
+        }
+    }
+
+    println!("# ID\tTIMESTAMP\tLAST APPROVAL");
+    for orphan in orphans {
+        println!("{}", orphan);
+    }
+
+    process::exit(0);
+}
+
+/// Print the largest number of mutually unreachable transactions, in the whole graph and
+/// in windows of time if requested, along with the number of transactions at each depth.
+fn width(input: &str, window: Option<NonZeroU64>) -> ! {
+    let graph = load(input);
+    validate(&graph);
+
+    let stats: Vec<Box<dyn Stat>> = vec![Box::new(Width::new(&graph))];
+    print_results(stats::accumulate(&graph, stats));
+
+    println!("# DEPTH\tTXS");
+    for (depth, width) in graph.level_widths().iter().enumerate() {
+        println!("{}\t{}", depth, width);
+    }
+
+    if let Some(size) = window {
+        println!("# START\tWIDTH");
+        for w in graph.window_widths(size) {
+            println!("{}", w);
+        }
+    }
+
+    process::exit(0);
+}
+
+/// Declare `ms` as the time unit of a graph when timestamps were given on the command line
+/// as dates and times, exiting when the graph declares another unit.
+fn declare_date_times(graph: &mut Graph, timestamps: &[TimestampArg]) {
+    if timestamps.iter().any(|t| t.date_time) {
+        if let Err(e) = time::declare_date_times(graph.metadata_mut()) {
+            error!("{}", e);
+            process::exit(1);
+        }
+    }
+}
+
+/// Write a graph to a file, or to the standard output, exiting when it cannot be written.
+fn write(graph: &Graph, output: Option<&str>) {
+    let output: io::Result<Box<dyn Write>> = match output {
+        Some(output) => File::create(output).map(|file| Box::new(file) as Box<dyn Write>),
+        None => Ok(Box::new(io::stdout().lock())),
+    };
+    let res = output.and_then(|output| {
+        let mut output = BufWriter::new(output);
+        graph.write(&mut output)?;
+        output.flush()
+    });
+
+    if let Err(e) = res {
+        error!("Error writing graph: {}", e);
+        process::exit(1);
+    }
+}
+
+fn open(input: &str) -> File {
+    File::open(input).unwrap_or_else(|e| {
+        error!("Error opening file `{}`: {}", input, e);
+        process::exit(1);
+    })
+}
+
+/// Load a graph with the default reader, exiting when it cannot be read.
+fn load(input: &str) -> Graph {
+    Graph::try_from_file(open(input), 1).unwrap_or_else(|e| {
+        error!("Error reading graph from `{}`:\n{}", input, e.render(input));
+        process::exit(2);
+    })
+}
+
+/// Unwrap the result of a calculation, exiting when it overflows.
+fn calculated<T>(result: Result<T, PosOverflow<usize>>) -> T {
+    result.unwrap_or_else(|e| {
+        error!("Error calculating result: {}", e);
+        process::exit(1);
+    })
+}
+
+fn print_results(results: Result<Vec<Box<dyn StatResult>>, PosOverflow<usize>>) {
+    match results {
+        Ok(results) => {
+            for r in results {
+                println!("{}", r);
+            }
+        }
+        Err(e) => {
+            error!("Error calculating result: {}", e);
+            process::exit(1);
+        }
+    }
+}
+
+fn print_windows(windows: Result<Vec<Window>, PosOverflow<usize>>) {
+    match windows {
+        Ok(windows) => {
+            println!("# START\tTXS\tAVG DEPTH\tAVG APPROVALS\tTIPS");
+            for w in windows {
+                println!("{}", w);
+            }
+        }
+        Err(e) => {
+            error!("Error calculating result: {}", e);
+            process::exit(1);
+        }
+    }
+}
+
+fn print_changes(changes: Result<Vec<StatChange>, PosOverflow<usize>>) {
+    match changes {
+        Ok(changes) => {
+            for change in changes {
+                println!("{}", change);
             }
         }
+        Err(e) => {
+            error!("Error calculating result: {}", e);
+            process::exit(1);
+        }
     }
 }
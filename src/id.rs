@@ -4,6 +4,7 @@
 
 use derive_more::Display;
 use std::convert::TryFrom;
+use std::error;
 use std::num::NonZeroUsize;
 
 /// Errors when dealing with transaction IDs.
@@ -16,6 +17,8 @@ pub enum Error {
     Reserved,
 }
 
+impl error::Error for Error {}
+
 /// The ID of a transaction that is not the root. This mainly exists to index into the
 /// `Graph` structure without the risk of trying to index the Root transaction.
 #[derive(Clone, Copy, PartialEq, Eq, Hash, Debug, Display)]
@@ -0,0 +1,314 @@
+#![warn(clippy::all)]
+
+//! Statistics that are kept up to date as transactions are pushed to a `Graph`.
+
+use crate::arrivals::Arrivals;
+use crate::graph::Graph;
+use crate::id::{Id, NonRootId};
+use crate::metadata::Metadata;
+use crate::stats::{self, DepthsResult, InReferencesResult, Stat, StatResult};
+use crate::time::TimeUnit;
+use crate::transaction::Transaction;
+use conv::{errors::PosOverflow, ValueFrom};
+use std::collections::{HashMap as Map, HashSet as Set};
+
+/// A statistic that is updated with each transaction pushed to a `Graph` instead of being
+/// accumulated over all of its transactions at once.
+pub trait Observer {
+    /// Update the statistic with a transaction that was just pushed to the graph.
+    fn observe(&mut self, graph: &Graph, transaction: &Transaction);
+
+    /// The current value of the statistic, like `Stat::result`.
+    fn result(&self, n_transactions: f64) -> Result<Box<dyn StatResult>, PosOverflow<usize>>;
+}
+
+/// A `Graph` along with the statistics that subscribed to the transactions pushed to it.
+pub struct ObservedGraph {
+    graph: Graph,
+    observers: Vec<Box<dyn Observer>>,
+}
+
+impl ObservedGraph {
+    pub fn new(graph: Graph) -> Self {
+        Self {
+            graph,
+            observers: Vec::new(),
+        }
+    }
+
+    pub fn graph(&self) -> &Graph {
+        &self.graph
+    }
+
+    /// The metadata of the graph, which the statistics do not depend on.
+    pub fn metadata_mut(&mut self) -> &mut Metadata {
+        self.graph.metadata_mut()
+    }
+
+    /// Subscribe a statistic to the transactions pushed to the graph. The statistic first
+    /// observes the transactions that are already in the graph.
+    pub fn subscribe(&mut self, mut observer: Box<dyn Observer>) {
+        for transaction in self.graph.transactions() {
+            observer.observe(&self.graph, transaction);
+        }
+
+        self.observers.push(observer);
+    }
+
+    /// Push a transaction to the graph and notify the subscribed statistics.
+    pub fn push(&mut self, transaction: Transaction) {
+        let id = transaction.id();
+        self.graph.push(transaction);
+
+        for observer in &mut self.observers {
+            observer.observe(&self.graph, &self.graph[id]);
+        }
+    }
+
+    /// The current values of the subscribed statistics, in the order they subscribed.
+    pub fn results(&self) -> Result<Vec<Box<dyn StatResult>>, PosOverflow<usize>> {
+        let n_transactions = f64::value_from(self.graph.len())?;
+        self.observers
+            .iter()
+            .map(|observer| observer.result(n_transactions))
+            .collect()
+    }
+}
+
+/// The incremental counterpart of `stats::Depths`. The depth of a transaction is computed
+/// from the depths of its references when it is pushed. Transactions that refer to
+/// transactions pushed later are buffered until those are pushed, and are left out of the
+/// statistic until then.
+#[derive(Default)]
+pub struct Depths {
+    /// The depths of the transactions, indexed by ID - 2, or None while buffered.
+    depths: Vec<Option<usize>>,
+
+    /// The buffered transactions, keyed by the reference they are waiting for.
+    waiting: Map<NonRootId, Vec<NonRootId>>,
+
+    /// The sum of all transaction depths.
+    sum_of_depths: usize,
+
+    /// The set of unique depth values.
+    unique_depths: Set<usize>,
+}
+
+impl Depths {
+    fn depth(&self, id: Id) -> Option<usize> {
+        match id {
+            Id::Root => Some(0),
+            Id::Transaction(id) => {
+                let index: usize = id.into();
+                self.depths.get(index - 2).copied().flatten()
+            }
+        }
+    }
+
+    fn wait(&mut self, missing: Id, id: NonRootId) {
+        if let Id::Transaction(missing) = missing {
+            self.waiting.entry(missing).or_default().push(id);
+        }
+    }
+}
+
+impl Observer for Depths {
+    fn observe(&mut self, graph: &Graph, transaction: &Transaction) {
+        self.depths.push(None);
+        let mut ready = vec![transaction.id()];
+
+        while let Some(id) = ready.pop() {
+            let t = &graph[id];
+
+            match (self.depth(t.left()), self.depth(t.right())) {
+                (Some(left), Some(right)) => {
+                    // The depth is the shorter path of the two possibilities. Release the
+                    // transactions that were waiting for this one.
+                    let depth = left.min(right) + 1;
+                    let index: usize = id.into();
+                    self.depths[index - 2] = Some(depth);
+                    self.sum_of_depths += depth;
+                    self.unique_depths.insert(depth);
+
+                    if let Some(waiting) = self.waiting.remove(&id) {
+                        ready.extend(waiting);
+                    }
+                }
+                (None, _) => self.wait(t.left(), id),
+                (_, None) => self.wait(t.right(), id),
+            }
+        }
+    }
+
+    fn result(&self, n_transactions: f64) -> Result<Box<dyn StatResult>, PosOverflow<usize>> {
+        let n_unique_depths = f64::value_from(self.unique_depths.len())?;
+        let sum_of_depths = f64::value_from(self.sum_of_depths)?;
+        Ok(Box::new(DepthsResult {
+            average_depth: sum_of_depths / (n_transactions + 1.0),
+            average_txs_per_depth: n_transactions / n_unique_depths,
+        }))
+    }
+}
+
+impl Observer for stats::Throughput {
+    fn observe(&mut self, graph: &Graph, transaction: &Transaction) {
+        if self.unit.is_none() {
+            self.unit = Some(TimeUnit::of(graph));
+        }
+        self.accumulate(transaction);
+    }
+
+    fn result(&self, n_transactions: f64) -> Result<Box<dyn StatResult>, PosOverflow<usize>> {
+        Stat::result(self, n_transactions)
+    }
+}
+
+impl Observer for Arrivals {
+    fn observe(&mut self, graph: &Graph, transaction: &Transaction) {
+        if self.unit.is_none() {
+            self.unit = Some(TimeUnit::of(graph));
+        }
+        self.accumulate(transaction);
+    }
+
+    fn result(&self, n_transactions: f64) -> Result<Box<dyn StatResult>, PosOverflow<usize>> {
+        Stat::result(self, n_transactions)
+    }
+}
+
+impl Observer for stats::Timestamps {
+    fn observe(&mut self, _graph: &Graph, transaction: &Transaction) {
+        self.accumulate(transaction);
+    }
+
+    fn result(&self, n_transactions: f64) -> Result<Box<dyn StatResult>, PosOverflow<usize>> {
+        Stat::result(self, n_transactions)
+    }
+}
+
+/// The incremental counterpart of `stats::InReferences`.
+#[derive(Default)]
+pub struct InReferences {
+    /// The total number of references to the Root and to the pushed transactions.
+    total_references: usize,
+
+    /// The number of references to transactions that were not pushed yet, keyed by the
+    /// referred transaction.
+    pending: Map<Id, usize>,
+}
+
+impl Observer for InReferences {
+    fn observe(&mut self, _graph: &Graph, transaction: &Transaction) {
+        let id = Id::Transaction(transaction.id());
+
+        // References to the transaction that were made before it was pushed.
+        self.total_references += self.pending.remove(&id).unwrap_or(0);
+
+        // References to the Root, to transactions pushed earlier or to the transaction
+        // itself count right away, the others once their transaction is pushed.
+        for &reference in &[transaction.left(), transaction.right()] {
+            if usize::from(reference) <= usize::from(id) {
+                self.total_references += 1;
+            } else {
+                *self.pending.entry(reference).or_default() += 1;
+            }
+        }
+    }
+
+    fn result(&self, n_transactions: f64) -> Result<Box<dyn StatResult>, PosOverflow<usize>> {
+        let total_references = f64::value_from(self.total_references)?;
+        Ok(Box::new(InReferencesResult {
+            average_references: total_references / (n_transactions + 1.0),
+        }))
+    }
+}
+
+#[cfg(test)]
+mod incremental_tests {
+    use super::{Depths, InReferences, ObservedGraph};
+    use crate::graph::Graph;
+    use crate::stats::{self, Stat};
+    use crate::transaction::Transaction;
+    use conv::ValueFrom;
+    use std::convert::TryFrom;
+    use std::io::BufReader;
+
+    fn batch_results(graph: &Graph) -> Vec<String> {
+        let mut stats: Vec<Box<dyn Stat>> = vec![
+            Box::new(stats::Depths::new(graph)),
+            Box::new(stats::InReferences::new(graph)),
+            Box::new(stats::Throughput::new(graph)),
+            Box::new(stats::Timestamps::new(graph)),
+        ];
+
+        for transaction in graph.transactions() {
+            for stat in &mut stats {
+                stat.accumulate(transaction);
+            }
+        }
+
+        let n_transactions = f64::value_from(graph.len()).unwrap();
+        stats
+            .iter()
+            .map(|stat| stat.result(n_transactions).unwrap().to_string())
+            .collect()
+    }
+
+    fn copy(t: &Transaction) -> Transaction {
+        Transaction::new(t.id(), t.left(), t.right(), t.timestamp())
+    }
+
+    fn observed() -> ObservedGraph {
+        let mut observed = ObservedGraph::new(Graph::with_capacity(0));
+        observed.subscribe(Box::new(Depths::default()));
+        observed.subscribe(Box::new(InReferences::default()));
+        observed.subscribe(Box::new(stats::Throughput::default()));
+        observed.subscribe(Box::new(stats::Timestamps::default()));
+        observed
+    }
+
+    fn results(observed: &ObservedGraph) -> Vec<String> {
+        let results = observed.results().unwrap();
+        results.iter().map(ToString::to_string).collect()
+    }
+
+    #[test]
+    fn same_as_batch() {
+        // Transactions 3 and 5 refer to transactions that come after them.
+        let input = "6\n1 1 120\n4 2 130\n2 1 130\n2 6 150\n1 4 160\n6 3 170\n";
+        let graph = Graph::try_from(BufReader::new(input.as_bytes())).unwrap();
+        let mut observed = observed();
+
+        for (i, transaction) in graph.transactions().enumerate() {
+            observed.push(copy(transaction));
+
+            let prefix = input.lines().skip(1).take(i + 1);
+            let prefix = format!("{}\n{}", i + 1, prefix.collect::<Vec<_>>().join("\n"));
+            if let Ok(expected) = Graph::try_from(BufReader::new(prefix.as_bytes())) {
+                if expected.is_connected_acyclic() == Some(true) {
+                    assert_eq!(results(&observed), batch_results(&expected));
+                }
+            }
+        }
+
+        assert_eq!(observed.graph(), &graph);
+        assert_eq!(results(&observed), batch_results(&graph));
+    }
+
+    #[test]
+    fn subscribe_late() {
+        let input = "3\n1 1 120\n2 1 130\n2 3 140\n";
+        let graph = Graph::try_from(BufReader::new(input.as_bytes())).unwrap();
+
+        let mut observed = ObservedGraph::new(Graph::with_capacity(0));
+        let mut transactions = graph.transactions().map(copy);
+        observed.push(transactions.next().unwrap());
+        observed.subscribe(Box::new(Depths::default()));
+        observed.push(transactions.next().unwrap());
+        observed.subscribe(Box::new(InReferences::default()));
+        observed.push(transactions.next().unwrap());
+
+        let expected = batch_results(&graph);
+        assert_eq!(results(&observed), expected[..2].to_vec());
+    }
+}
@@ -0,0 +1,102 @@
+#![warn(clippy::all)]
+
+//! Reading the meaningful lines of text inputs.
+
+use std::io::{self, BufRead};
+
+/// Iterator over the meaningful lines of an input along with their line numbers. Comments
+/// starting with `#` are stripped from lines and blank lines are skipped.
+pub struct Lines<B> {
+    inner: io::Lines<B>,
+
+    /// The number of the last line read, starting at 1.
+    line_no: usize,
+}
+
+impl<B: BufRead> Lines<B> {
+    pub fn new(input: B) -> Self {
+        Self {
+            inner: input.lines(),
+            line_no: 0,
+        }
+    }
+
+    /// The number of the last line read, including skipped lines.
+    pub fn line_no(&self) -> usize {
+        self.line_no
+    }
+}
+
+impl<B: BufRead> Iterator for Lines<B> {
+    type Item = io::Result<(usize, String)>;
+
+    fn next(&mut self) -> Option<Self::Item> {
+        loop {
+            let mut line = match self.inner.next()? {
+                Ok(line) => line,
+                Err(e) => return Some(Err(e)),
+            };
+            self.line_no += 1;
+
+            line.truncate(strip_comment(&line).len());
+            if !line.trim().is_empty() {
+                return Some(Ok((self.line_no, line)));
+            }
+        }
+    }
+}
+
+/// Strip the comment starting with `#` from a line, if any.
+fn strip_comment(line: &str) -> &str {
+    match line.find('#') {
+        Some(comment) => &line[..comment],
+        None => line,
+    }
+}
+
+/// The meaningful lines of an input held in memory along with their line numbers,
+/// starting at 1, like `Lines` but borrowing the raw bytes of the input instead of reading
+/// it. Lines are not checked to be valid UTF-8.
+pub fn byte_lines(input: &[u8]) -> impl Iterator<Item = (usize, &[u8])> {
+    input
+        .split(|&b| b == b'\n')
+        .enumerate()
+        .map(|(i, line)| (i + 1, strip_comment_bytes(line)))
+        .filter(|(_, line)| !line.iter().all(u8::is_ascii_whitespace))
+}
+
+/// Strip the comment starting with `#` and the line ending from a line, if any.
+fn strip_comment_bytes(line: &[u8]) -> &[u8] {
+    let line = line.strip_suffix(b"\r").unwrap_or(line);
+    match line.iter().position(|&b| b == b'#') {
+        Some(comment) => &line[..comment],
+        None => line,
+    }
+}
+
+#[cfg(test)]
+mod lines_tests {
+    use super::{byte_lines, Lines};
+
+    #[test]
+    fn lines() {
+        let input = "# A comment\n2\n\n1 1 120 # The first transaction\n  \n2 1 130";
+        let mut lines = Lines::new(input.as_bytes());
+        assert_eq!(lines.next().unwrap().unwrap(), (2, String::from("2")));
+        assert_eq!(
+            lines.next().unwrap().unwrap(),
+            (4, String::from("1 1 120 "))
+        );
+        assert_eq!(lines.next().unwrap().unwrap(), (6, String::from("2 1 130")));
+        assert!(lines.next().is_none());
+        assert_eq!(lines.line_no(), 6);
+
+        let lines: Vec<(usize, &[u8])> = byte_lines(input.as_bytes()).collect();
+        let expected: Vec<(usize, &[u8])> = vec![(2, b"2"), (4, b"1 1 120 "), (6, b"2 1 130")];
+        assert_eq!(lines, expected);
+
+        let lines: Vec<(usize, &[u8])> = byte_lines(b"1 1 120\r\n\r\n2 1 130\r\n").collect();
+        let expected: Vec<(usize, &[u8])> = vec![(1, b"1 1 120"), (3, b"2 1 130")];
+        assert_eq!(lines, expected);
+    }
+}
@@ -1,12 +1,36 @@
+pub mod anomalies;
+pub mod arrivals;
+pub mod canonical;
+pub mod diff;
+pub mod dominators;
+pub mod extract;
+pub mod follow;
 pub mod graph;
 mod id;
+pub mod incremental;
+mod input;
+pub mod location;
+pub mod metadata;
+mod mmap;
+pub mod names;
+pub mod orphans;
+mod parallel;
+pub mod parents;
+pub mod prune;
+pub mod reduction;
+pub mod solidify;
 pub mod stats;
+pub mod time;
 mod transaction;
+pub mod trunk;
+pub mod width;
+pub mod window;
 
 #[cfg(test)]
 mod tests {
-    use super::graph::Graph;
+    use super::graph::{DepthPath, Graph};
     use super::stats::{self, Stat};
+    use super::time::TimeUnit;
     use crate::id::{Id, NonRootId};
     use crate::transaction::Transaction;
     use conv::ValueFrom;
@@ -72,7 +96,7 @@ mod tests {
         let mut stats: Vec<Box<dyn Stat>> = vec![
             Box::new(stats::Depths::new(&graph)),
             Box::new(stats::InReferences::new(&graph)),
-            Box::new(stats::TimeUnits::default()),
+            Box::new(stats::Throughput::new(&graph)),
             Box::new(stats::Timestamps::new(&graph)),
         ];
 
@@ -92,10 +116,61 @@ mod tests {
 
         let res = stats[2].result(n_transactions).unwrap();
         let res = format!("{}", res);
-        assert_eq!(res, "> AVG TXS PER TIME UNIT: 0.60");
+        assert_eq!(
+            res,
+            "> THROUGHPUT: 1.33 TXS/tick\n> AVG INTER-ARRIVAL TIME: 0.75 ticks"
+        );
 
         let res = stats[3].result(n_transactions).unwrap();
         let res = format!("{}", res);
         assert_eq!(res, "> AVG TXS PER TIMESTAMP: 1.25");
     }
+
+    #[test]
+    fn heights() {
+        let graph = graph();
+        let stats: Vec<Box<dyn Stat>> = vec![
+            Box::new(stats::Heights::new(&graph)),
+            Box::new(stats::Depths::with_path(&graph, DepthPath::Longest)),
+        ];
+        let results = stats::accumulate(&graph, stats).unwrap();
+
+        assert_eq!(
+            results[0].to_string(),
+            "> AVG HEIGHT (INCL. ROOT): 2.00\n\
+             > HEIGHTS: MIN 1, MEDIAN 2, P90 4, MAX 4\n\
+             > AVG HEIGHT - DEPTH (INCL. ROOT): 0.67\n\
+             > HEIGHT - DEPTH: MIN 0, MEDIAN 1, P90 2, MAX 2"
+        );
+        assert_eq!(
+            results[1].to_string(),
+            "> AVG DAG DEPTH: 2.00\n> AVG TXS PER DEPTH: 1.25"
+        );
+    }
+
+    #[test]
+    fn throughput_units() {
+        let input = "time-unit: ms\n3\n1 1 100\n2 1 150\n3 2 400\n";
+        let graph = Graph::try_from(BufReader::new(input.as_bytes())).unwrap();
+        let n_transactions = f64::value_from(graph.len()).unwrap();
+
+        let mut throughput = stats::Throughput::new(&graph);
+        for transaction in graph.transactions() {
+            throughput.accumulate(transaction);
+        }
+        let res = throughput.result(n_transactions).unwrap();
+        assert_eq!(
+            res.to_string(),
+            "> THROUGHPUT: 6.67 TXS/s\n> AVG INTER-ARRIVAL TIME: 150.00 ms"
+        );
+
+        // A single transaction spans no time and no intervals.
+        let mut throughput = stats::Throughput::with_unit(TimeUnit::Seconds);
+        throughput.accumulate(graph.transactions().next().unwrap());
+        let res = throughput.result(1.0).unwrap();
+        assert_eq!(
+            res.to_string(),
+            "> THROUGHPUT: NaN TXS/s\n> AVG INTER-ARRIVAL TIME: NaN s"
+        );
+    }
 }
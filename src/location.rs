@@ -0,0 +1,136 @@
+#![warn(clippy::all)]
+
+//! Locations of errors in inputs and their rustc-style rendering.
+
+use derive_more::Display;
+use std::error::Error;
+use std::fmt::Write;
+use std::ops::Range;
+
+/// The location of some offending text in an input.
+#[derive(Clone, PartialEq, Eq, Debug, Display)]
+#[display(fmt = "{}:{}", line, "columns.start + 1")]
+pub struct Location {
+    /// The line number, starting at 1.
+    pub line: usize,
+
+    /// The byte range of the offending text within the line.
+    pub columns: Range<usize>,
+
+    /// The text of the whole line.
+    pub text: String,
+}
+
+impl Location {
+    pub fn new(line: usize, columns: Range<usize>, text: &str) -> Self {
+        Self {
+            line,
+            columns,
+            text: String::from(text),
+        }
+    }
+
+    /// The location of a whole line.
+    pub fn line(line: usize, text: &str) -> Self {
+        Self::new(line, 0..text.len(), text)
+    }
+
+    /// The location of the `index`-th whitespace-separated token of a line, or the end of
+    /// the line when the line has less tokens.
+    pub fn token(line: usize, text: &str, index: usize) -> Self {
+        Self::new(line, token_span(text, index), text)
+    }
+
+    /// The offending text.
+    pub fn offending(&self) -> &str {
+        &self.text[self.columns.clone()]
+    }
+
+    /// Render an error at this location in the style of rustc, with a snippet of the
+    /// offending line. The messages of the error's sources are appended to its own.
+    pub fn render(&self, error: &dyn Error, path: &str) -> String {
+        let mut output = String::new();
+        let gutter = " ".repeat(self.line.to_string().len());
+        let underline = "^".repeat(self.columns.len().max(1));
+
+        // Writing to a String cannot fail.
+        writeln!(output, "error: {}", chain(error)).unwrap();
+        writeln!(output, "{}--> {}:{}", gutter, path, self).unwrap();
+        writeln!(output, "{} |", gutter).unwrap();
+        writeln!(output, "{} | {}", self.line, self.text).unwrap();
+        write!(
+            output,
+            "{} | {}{}",
+            gutter,
+            " ".repeat(self.columns.start),
+            underline
+        )
+        .unwrap();
+
+        output
+    }
+}
+
+/// The message of an error followed by the messages of all of its sources.
+pub fn chain(error: &dyn Error) -> String {
+    let mut message = error.to_string();
+    let mut source = error.source();
+
+    while let Some(e) = source {
+        message.push_str(": ");
+        message.push_str(&e.to_string());
+        source = e.source();
+    }
+
+    message
+}
+
+/// The byte range of the `index`-th whitespace-separated token of a line, or an empty range
+/// at the end of the line when the line has less tokens.
+fn token_span(text: &str, index: usize) -> Range<usize> {
+    let mut tokens = text
+        .char_indices()
+        .filter(|(_, c)| !c.is_ascii_whitespace())
+        .map(|(i, _)| i)
+        .filter(|&i| i == 0 || text.as_bytes()[i - 1].is_ascii_whitespace());
+
+    match tokens.nth(index) {
+        Some(start) => {
+            let len = text[start..]
+                .find(|c: char| c.is_ascii_whitespace())
+                .unwrap_or(text.len() - start);
+            start..start + len
+        }
+        None => text.len()..text.len(),
+    }
+}
+
+#[cfg(test)]
+mod location_tests {
+    use super::Location;
+    use std::num::ParseIntError;
+
+    #[test]
+    fn token() {
+        let text = "  12 abc\t7";
+        assert_eq!(Location::token(3, text, 0).offending(), "12");
+        assert_eq!(Location::token(3, text, 1).offending(), "abc");
+        assert_eq!(Location::token(3, text, 2).offending(), "7");
+        assert_eq!(Location::token(3, text, 3).columns, 10..10);
+        assert_eq!(Location::token(3, text, 1).to_string(), "3:6");
+    }
+
+    #[test]
+    fn render() {
+        let error: ParseIntError = "abc".parse::<usize>().err().unwrap();
+        let location = Location::token(12, "1 abc 0", 1);
+        let expected = concat!(
+            "error: invalid digit found in string\n",
+            "  --> input.in:12:3\n",
+            "   |\n",
+            "12 | 1 abc 0\n",
+            "   |   ^^^",
+        );
+        assert_eq!(location.render(&error, "input.in"), expected);
+    }
+}
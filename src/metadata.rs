@@ -0,0 +1,111 @@
+#![warn(clippy::all)]
+
+//! Metadata about the provenance of graphs.
+
+use std::io::{self, Write};
+
+/// The `key: value` metadata found in the header of an input, in the order it was found.
+#[derive(Clone, PartialEq, Eq, Debug, Default)]
+pub struct Metadata {
+    entries: Vec<(String, String)>,
+}
+
+impl Metadata {
+    pub const GENERATOR: &'static str = "generator";
+    pub const SEED: &'static str = "seed";
+    pub const TIME_UNIT: &'static str = "time-unit";
+    pub const SOURCE: &'static str = "source";
+
+    pub fn len(&self) -> usize {
+        self.entries.len()
+    }
+
+    pub fn is_empty(&self) -> bool {
+        self.entries.is_empty()
+    }
+
+    pub fn get(&self, key: &str) -> Option<&str> {
+        self.entries
+            .iter()
+            .find(|(k, _)| k == key)
+            .map(|(_, v)| v.as_str())
+    }
+
+    /// Set the value of a key, replacing any previous value.
+    pub fn insert(&mut self, key: &str, value: &str) {
+        match self.entries.iter_mut().find(|(k, _)| k == key) {
+            Some((_, v)) => *v = String::from(value),
+            None => self.entries.push((String::from(key), String::from(value))),
+        }
+    }
+
+    pub fn iter(&self) -> impl Iterator<Item = (&str, &str)> {
+        self.entries.iter().map(|(k, v)| (k.as_str(), v.as_str()))
+    }
+
+    /// The tool that generated the input.
+    pub fn generator(&self) -> Option<&str> {
+        self.get(Self::GENERATOR)
+    }
+
+    /// The seed of the random number generator that generated the input.
+    pub fn seed(&self) -> Option<&str> {
+        self.get(Self::SEED)
+    }
+
+    /// The unit of the timestamps in the input.
+    pub fn time_unit(&self) -> Option<&str> {
+        self.get(Self::TIME_UNIT)
+    }
+
+    /// Where the input was collected from.
+    pub fn source(&self) -> Option<&str> {
+        self.get(Self::SOURCE)
+    }
+
+    /// Write the metadata as a header of `key: value` lines.
+    pub fn write<W: Write>(&self, output: &mut W) -> io::Result<()> {
+        for (key, value) in self.iter() {
+            writeln!(output, "{}: {}", key, value)?;
+        }
+        Ok(())
+    }
+
+    /// Split a `key: value` header line, returning None when the line is not metadata.
+    pub(crate) fn split_line(line: &str) -> Option<(&str, &str)> {
+        let colon = line.find(':')?;
+        Some((line[..colon].trim(), line[colon + 1..].trim()))
+    }
+}
+
+#[cfg(test)]
+mod metadata_tests {
+    use super::Metadata;
+
+    #[test]
+    fn insert() {
+        let mut metadata = Metadata::default();
+        metadata.insert(Metadata::GENERATOR, "bpdaggen");
+        metadata.insert(Metadata::SEED, "42");
+        metadata.insert(Metadata::SEED, "7");
+        assert_eq!(metadata.len(), 2);
+        assert_eq!(metadata.generator(), Some("bpdaggen"));
+        assert_eq!(metadata.seed(), Some("7"));
+        assert_eq!(metadata.source(), None);
+
+        let mut output = Vec::new();
+        metadata.write(&mut output).unwrap();
+        assert_eq!(output, b"generator: bpdaggen\nseed: 7\n");
+    }
+
+    #[test]
+    fn split_line() {
+        assert_eq!(
+            Metadata::split_line("time-unit:  ms "),
+            Some(("time-unit", "ms"))
+        );
+        assert_eq!(Metadata::split_line("source: "), Some(("source", "")));
+        assert_eq!(Metadata::split_line(" : ms"), Some(("", "ms")));
+        assert_eq!(Metadata::split_line("5"), None);
+    }
+}
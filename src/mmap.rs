@@ -0,0 +1,69 @@
+#![warn(clippy::all)]
+
+//! Reading of graphs from files mapped in memory.
+
+use crate::graph::{Error, Graph};
+use memmap2::Mmap;
+use std::convert::TryFrom;
+use std::fs::File;
+use std::io::BufReader;
+
+impl Graph {
+    /// Read a `Graph` from a file like `Graph::try_from`. Regular files are mapped in
+    /// memory and parsed in place on `jobs` threads with `Graph::try_from_parallel`,
+    /// without copying or allocating for each line. Files that cannot be mapped, like
+    /// pipes, are read sequentially instead.
+    pub fn try_from_file(file: File, jobs: usize) -> Result<Self, Error> {
+        if !file.metadata()?.is_file() {
+            return Graph::try_from(BufReader::new(file));
+        }
+
+        // Safety: the mapping is only read while parsing and is dropped before returning.
+        // Inputs being truncated or written to by another process while they are read is
+        // not supported, as with any other reader.
+        let input = unsafe { Mmap::map(&file)? };
+        Graph::try_from_parallel(&input, jobs)
+    }
+}
+
+#[cfg(test)]
+mod mmap_tests {
+    use crate::graph::{Error, Graph};
+    use std::convert::TryFrom;
+    use std::env;
+    use std::fs::{self, File};
+    use std::io::BufReader;
+    use std::path::PathBuf;
+
+    fn temp_file(name: &str, contents: &str) -> PathBuf {
+        let path = env::temp_dir().join(format!("graphstats-{}-{}", std::process::id(), name));
+        fs::write(&path, contents).unwrap();
+        path
+    }
+
+    #[test]
+    fn same_as_sequential() {
+        let input = "# A graph\n3\n1 1 120\n2 1 130\n\n2 2 140\n";
+        let path = temp_file("mmap.in", input);
+        let expected = Graph::try_from(BufReader::new(input.as_bytes())).unwrap();
+
+        for jobs in 1..4 {
+            let graph = Graph::try_from_file(File::open(&path).unwrap(), jobs).unwrap();
+            assert_eq!(graph, expected);
+        }
+
+        fs::remove_file(&path).unwrap();
+    }
+
+    #[test]
+    fn empty() {
+        let path = temp_file("empty.in", "");
+
+        match Graph::try_from_file(File::open(&path).unwrap(), 1) {
+            Err(Error::MissingNumberOfTransactions(location)) => assert_eq!(location.line, 1),
+            res => panic!("Unexpected result: {:?}", res.map(|g| g.len())),
+        }
+
+        fs::remove_file(&path).unwrap();
+    }
+}
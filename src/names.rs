@@ -0,0 +1,120 @@
+#![warn(clippy::all)]
+
+//! Interning of textual transaction identifiers into dense IDs.
+
+use crate::id::{Id, NonRootId};
+use derive_more::Display;
+use std::collections::HashMap as Map;
+use std::convert::TryFrom;
+use std::error;
+
+/// Errors that can happen when interning transaction identifiers.
+#[derive(PartialEq, Eq, Debug, Display)]
+pub enum Error {
+    #[display(fmt = "Duplicate transaction identifier `{}`", "_0")]
+    Duplicate(String),
+
+    #[display(fmt = "Transaction identifier `{}` is reserved for Root", "_0")]
+    Reserved(String),
+}
+
+impl error::Error for Error {}
+
+/// The original identifiers of the transactions in a `Graph`. Identifiers are interned in
+/// the order they are defined so that the n-th defined identifier gets the n-th
+/// `NonRootId`, which keeps the `Graph` vector of transactions dense.
+#[derive(PartialEq, Eq, Debug)]
+pub struct Names {
+    /// The identifiers, indexed by `Id` - 1 so that the Root identifier comes first.
+    names: Vec<String>,
+
+    /// The reverse lookup from identifiers to their interned `Id`s.
+    ids: Map<String, Id>,
+}
+
+impl Names {
+    pub fn new(root: &str) -> Self {
+        let mut ids = Map::new();
+        ids.insert(String::from(root), Id::Root);
+
+        Self {
+            names: vec![String::from(root)],
+            ids,
+        }
+    }
+
+    /// The number of interned identifiers, excluding the Root identifier.
+    pub fn len(&self) -> usize {
+        self.names.len() - 1
+    }
+
+    pub fn is_empty(&self) -> bool {
+        self.len() == 0
+    }
+
+    pub fn root(&self) -> &str {
+        &self.names[0]
+    }
+
+    /// Lookup the interned `Id` of an identifier.
+    pub fn get(&self, name: &str) -> Option<Id> {
+        self.ids.get(name).copied()
+    }
+
+    /// Lookup the original identifier of an interned `Id`.
+    pub fn name(&self, id: Id) -> Option<&str> {
+        let index: usize = id.into();
+        self.names.get(index - 1).map(String::as_str)
+    }
+
+    /// Intern a newly defined identifier, assigning it the next available `NonRootId`.
+    pub fn intern(&mut self, name: &str) -> Result<NonRootId, Error> {
+        match self.ids.get(name) {
+            Some(Id::Root) => return Err(Error::Reserved(String::from(name))),
+            Some(Id::Transaction(_)) => return Err(Error::Duplicate(String::from(name))),
+            None => {}
+        }
+
+        // The Root takes ID 1, so the next ID is one more than the number of identifiers.
+        let id = NonRootId::try_from(self.names.len() + 1)
+            .expect("Interned IDs always start after the Root ID");
+        self.names.push(String::from(name));
+        self.ids.insert(String::from(name), Id::from(id));
+        Ok(id)
+    }
+}
+
+#[cfg(test)]
+mod names_tests {
+    use super::{Error, Names};
+    use crate::id::{Id, NonRootId};
+    use std::convert::TryFrom;
+
+    #[test]
+    fn intern() {
+        let mut names = Names::new("genesis");
+        assert_eq!(names.intern("a3f"), Ok(NonRootId::try_from(2).unwrap()));
+        assert_eq!(names.intern("9bc"), Ok(NonRootId::try_from(3).unwrap()));
+        assert_eq!(names.len(), 2);
+        assert_eq!(names.get("genesis"), Some(Id::Root));
+        assert_eq!(names.get("9bc"), Some(Id::try_from(3).unwrap()));
+        assert_eq!(names.get("missing"), None);
+        assert_eq!(names.name(Id::Root), Some("genesis"));
+        assert_eq!(names.name(Id::try_from(2).unwrap()), Some("a3f"));
+        assert_eq!(names.name(Id::try_from(4).unwrap()), None);
+    }
+
+    #[test]
+    fn intern_invalid() {
+        let mut names = Names::new("genesis");
+        names.intern("a3f").unwrap();
+        assert_eq!(
+            names.intern("a3f"),
+            Err(Error::Duplicate(String::from("a3f")))
+        );
+        assert_eq!(
+            names.intern("genesis"),
+            Err(Error::Reserved(String::from("genesis")))
+        );
+    }
+}
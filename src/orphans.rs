@@ -0,0 +1,226 @@
+#![warn(clippy::all)]
+
+//! Detection of the transactions that are left behind: transactions that stop being
+//! approved, directly or not, and are therefore never confirmed.
+
+use crate::graph::Graph;
+use crate::id::{Id, NonRootId};
+use crate::time::Timestamp;
+use conv::{errors::PosOverflow, ValueFrom};
+use derive_more::Display;
+use std::collections::HashMap as Map;
+use std::fmt::{self, Display};
+use std::num::NonZeroU64;
+
+/// A transaction that nothing approves, directly or not, from the cutoff on.
+#[derive(PartialEq, Debug)]
+pub struct Orphan {
+    /// The ID of the transaction.
+    pub id: usize,
+
+    /// The timestamp of the transaction.
+    pub timestamp: Timestamp,
+
+    /// The timestamp of the last transaction approving it, directly or not, or `None` when
+    /// nothing ever approved it.
+    pub last_approval: Option<Timestamp>,
+}
+
+/// Displayed as a tab-separated row, with `-` for transactions that were never approved.
+impl Display for Orphan {
+    fn fmt(&self, f: &mut fmt::Formatter) -> fmt::Result {
+        write!(f, "{}\t{}\t", self.id, self.timestamp)?;
+        match self.last_approval {
+            Some(last_approval) => write!(f, "{}", last_approval),
+            None => write!(f, "-"),
+        }
+    }
+}
+
+/// The orphans among the transactions with a timestamp in a window of time, displayed as a
+/// tab-separated row.
+#[derive(PartialEq, Debug, Display)]
+#[display(fmt = "{}\t{}\t{}\t{:.2}", start, txs, orphans, rate)]
+pub struct OrphanRate {
+    /// The first timestamp of the window.
+    pub start: Timestamp,
+
+    /// The number of transactions issued in the window.
+    pub txs: usize,
+
+    /// The number of orphans issued in the window.
+    pub orphans: usize,
+
+    /// The share of the transactions of the window that are orphans, NaN for an empty
+    /// window.
+    pub rate: f64,
+}
+
+impl Graph {
+    /// The timestamp of the last transaction approving each transaction, directly or not,
+    /// i.e. the latest timestamp in its future cone. Transactions that nothing approves are
+    /// left out. Assumes the graph is acyclic.
+    pub fn last_approvals(&self) -> Map<NonRootId, Timestamp> {
+        // Visit transactions after all the transactions approving them, starting from the
+        // tips, and push the latest timestamps down to the parents.
+        let mut pending: Map<NonRootId, usize> = self
+            .transactions()
+            .map(|t| {
+                let references = self.references(Id::Transaction(t.id()));
+                (t.id(), references.map_or(0, |r| r.sources().count()))
+            })
+            .collect();
+        let mut stack: Vec<NonRootId> = pending
+            .iter()
+            .filter(|(_, &n)| n == 0)
+            .map(|(&id, _)| id)
+            .collect();
+        let mut last_approvals = Map::with_capacity(self.len());
+
+        while let Some(id) = stack.pop() {
+            let t = &self[id];
+            let latest = match last_approvals.get(&id) {
+                Some(&last_approval) => t.timestamp().max(last_approval),
+                None => t.timestamp(),
+            };
+
+            let mut parents = vec![t.left()];
+            if t.right() != t.left() {
+                parents.push(t.right());
+            }
+            for parent in parents {
+                if let Id::Transaction(parent) = parent {
+                    let last_approval = last_approvals.entry(parent).or_insert(latest);
+                    *last_approval = (*last_approval).max(latest);
+
+                    let n = pending.get_mut(&parent).expect("Parents are transactions");
+                    *n -= 1;
+                    if *n == 0 {
+                        stack.push(parent);
+                    }
+                }
+            }
+        }
+
+        last_approvals
+    }
+
+    /// The transactions issued before `cutoff` that nothing issued from `cutoff` on
+    /// approves, directly or not, sorted by ID. Their future cone stopped growing before
+    /// the cutoff, so they are likely never to be confirmed. Transactions issued from
+    /// `cutoff` on are too recent to tell.
+    pub fn orphans(&self, cutoff: Timestamp) -> Vec<Orphan> {
+        let last_approvals = self.last_approvals();
+
+        self.transactions()
+            .filter(|t| t.timestamp() < cutoff)
+            .filter_map(|t| {
+                let last_approval = last_approvals.get(&t.id()).copied();
+                match last_approval {
+                    Some(last_approval) if last_approval >= cutoff => None,
+                    _ => Some(Orphan {
+                        id: t.id().into(),
+                        timestamp: t.timestamp(),
+                        last_approval,
+                    }),
+                }
+            })
+            .collect()
+    }
+
+    /// Split the timestamps before `cutoff` into windows of `size` like `Graph::windows`,
+    /// and count the transactions and the orphans issued within each window.
+    pub fn orphan_rates(
+        &self,
+        cutoff: Timestamp,
+        size: NonZeroU64,
+    ) -> Result<Vec<OrphanRate>, PosOverflow<usize>> {
+        let bucket = |timestamp: Timestamp| timestamp / size.get();
+
+        let mut counts: Map<Timestamp, (usize, usize)> = Map::new();
+        for t in self.transactions().filter(|t| t.timestamp() < cutoff) {
+            counts.entry(bucket(t.timestamp())).or_default().0 += 1;
+        }
+        for orphan in self.orphans(cutoff) {
+            counts.entry(bucket(orphan.timestamp)).or_default().1 += 1;
+        }
+
+        let first = counts.keys().min().copied();
+        let last = counts.keys().max().copied();
+        let (first, last) = match (first, last) {
+            (Some(first), Some(last)) => (first, last),
+            _ => return Ok(Vec::new()),
+        };
+
+        (first..=last)
+            .map(|bucket| {
+                let (txs, orphans) = counts.get(&bucket).copied().unwrap_or_default();
+                Ok(OrphanRate {
+                    start: bucket * size.get(),
+                    txs,
+                    orphans,
+                    rate: f64::value_from(orphans)? / f64::value_from(txs)?,
+                })
+            })
+            .collect()
+    }
+}
+
+#[cfg(test)]
+mod orphans_tests {
+    use crate::graph::test_graph;
+    use std::num::NonZeroU64;
+
+    #[test]
+    fn orphans() {
+        // Transaction 7 is only approved by transaction 8, which nothing approves.
+        let tangle = test_graph("7\n1 1 0\n1 1 0\n2 3 10\n4 4 20\n5 1 30\n3 3 12\n7 7 15\n");
+
+        let last_approvals = tangle.last_approvals();
+        let mut last_approvals: Vec<(usize, u64)> = last_approvals
+            .iter()
+            .map(|(&id, &last_approval)| (id.into(), last_approval))
+            .collect();
+        last_approvals.sort_unstable();
+        assert_eq!(
+            last_approvals,
+            vec![(2, 30), (3, 30), (4, 30), (5, 30), (7, 15)]
+        );
+
+        let orphans: Vec<String> = tangle.orphans(25).iter().map(ToString::to_string).collect();
+        assert_eq!(orphans, vec!["7\t12\t15", "8\t15\t-"]);
+
+        // Everything issued before the last approval of transaction 7 is approved later.
+        assert!(tangle.orphans(15).is_empty());
+        assert!(tangle.orphans(0).is_empty());
+    }
+
+    #[test]
+    fn orphan_rates() {
+        let tangle = test_graph("7\n1 1 0\n1 1 0\n2 3 10\n4 4 20\n5 1 30\n3 3 12\n7 7 15\n");
+        let rates = |size| -> Vec<String> {
+            let rates = tangle.orphan_rates(25, NonZeroU64::new(size).unwrap());
+            let rates = rates.unwrap();
+            rates.iter().map(ToString::to_string).collect()
+        };
+
+        assert_eq!(
+            rates(10),
+            vec!["0\t2\t0\t0.00", "10\t3\t2\t0.67", "20\t1\t0\t0.00"]
+        );
+        assert_eq!(
+            rates(5),
+            vec![
+                "0\t2\t0\t0.00",
+                "5\t0\t0\tNaN",
+                "10\t2\t1\t0.50",
+                "15\t1\t1\t1.00",
+                "20\t1\t0\t0.00"
+            ]
+        );
+        assert!(test_graph("0\n")
+            .orphan_rates(25, NonZeroU64::new(10).unwrap())
+            .unwrap()
+            .is_empty());
+    }
+}
@@ -0,0 +1,340 @@
+#![warn(clippy::all)]
+
+//! Parallel reading of graphs from inputs held in memory.
+
+use crate::graph::{self, Error, Graph, ReverseReferences};
+use crate::id::NonRootId;
+use crate::input::byte_lines;
+use crate::location::Location;
+use crate::metadata::Metadata;
+use crate::time;
+use crate::transaction::{self, Transaction};
+use std::borrow::Cow;
+use std::convert::TryFrom;
+use std::io;
+use std::str;
+use std::thread;
+
+/// The transactions parsed out of a chunk of an input.
+struct Chunk<'a> {
+    /// The bytes of the chunk.
+    input: &'a [u8],
+
+    /// The number of lines in the chunk.
+    n_lines: usize,
+
+    /// The transactions parsed before the first error in the chunk, if any. The IDs of the
+    /// transactions are placeholders since they depend on the number of transactions in
+    /// the previous chunks.
+    transactions: Vec<Transaction>,
+
+    /// The first error in the chunk with the line it was found on, numbered from the start
+    /// of the chunk.
+    error: Option<(usize, &'a [u8], transaction::Error)>,
+
+    /// The index of the first transaction in the chunk with a date and time as its
+    /// timestamp, which declares the time unit of the graph.
+    first_date_time: Option<usize>,
+}
+
+impl<'a> Chunk<'a> {
+    fn parse(input: &'a [u8]) -> Self {
+        let n_lines = count_lines(input);
+        let mut transactions = Vec::with_capacity(n_lines);
+        let mut error = None;
+        let mut first_date_time = None;
+
+        for (line_no, line) in byte_lines(input) {
+            match Transaction::parse_bytes(2, line) {
+                Ok(t) => {
+                    if first_date_time.is_none() && has_date_time(line) {
+                        first_date_time = Some(transactions.len());
+                    }
+                    transactions.push(t);
+                }
+                Err(e) => {
+                    error = Some((line_no, line, e));
+                    break;
+                }
+            }
+        }
+
+        Self {
+            input,
+            n_lines,
+            transactions,
+            error,
+            first_date_time,
+        }
+    }
+
+    /// The line of the `index`-th transaction in the chunk, numbered from the start of the
+    /// chunk.
+    fn line(&self, index: usize) -> (usize, Cow<'a, str>) {
+        let (line_no, line) = byte_lines(self.input)
+            .nth(index)
+            .expect("The chunk contains the transaction");
+        (line_no, String::from_utf8_lossy(line))
+    }
+}
+
+/// Whether the timestamp of a `LID RID TIMESTAMP` line is a date and time. Dates and times
+/// are the only timestamps with colons.
+fn has_date_time(line: &[u8]) -> bool {
+    line.contains(&b':')
+        && line
+            .split(u8::is_ascii_whitespace)
+            .filter(|token| !token.is_empty())
+            .nth(2)
+            .and_then(|timestamp| str::from_utf8(timestamp).ok())
+            .is_some_and(time::is_date_time)
+}
+
+/// The number of lines in an input, including a last line without a line ending.
+fn count_lines(input: &[u8]) -> usize {
+    input.iter().filter(|&&b| b == b'\n').count()
+        + usize::from(input.last().is_some_and(|&b| b != b'\n'))
+}
+
+/// Split an input into at most `n` chunks of about the same size at line boundaries.
+fn split(input: &[u8], n: usize) -> Vec<&[u8]> {
+    let size = input.len() / n.max(1) + 1;
+    let mut chunks = Vec::with_capacity(n);
+    let mut start = 0;
+
+    while start < input.len() {
+        // Extend the chunk up to the end of the line it stops on.
+        let end = (start + size).min(input.len());
+        let end = match input[end..].iter().position(|&b| b == b'\n') {
+            Some(newline) => end + newline + 1,
+            None => input.len(),
+        };
+
+        chunks.push(&input[start..end]);
+        start = end;
+    }
+
+    chunks
+}
+
+impl Graph {
+    /// Read a `Graph` from an input held in memory like `Graph::try_from`, but parse the
+    /// transactions on `jobs` threads. The input is split into chunks at line boundaries
+    /// that are parsed independently, and the transactions are then numbered and checked
+    /// in order so that the result is identical to `Graph::try_from`, including the errors.
+    /// The reverse references are finally built on `jobs` threads as well, each for a range
+    /// of IDs. Transactions are parsed straight from the bytes of the input,
+    /// so that invalid UTF-8 in a transaction is reported as an invalid token.
+    pub fn try_from_parallel(input: &[u8], jobs: usize) -> Result<Self, Error> {
+        let mut metadata = Metadata::default();
+        let mut header = None;
+
+        // Read the header sequentially, it is at most a handful of lines.
+        for (line_no, line) in byte_lines(input) {
+            let line =
+                str::from_utf8(line).map_err(|e| io::Error::new(io::ErrorKind::InvalidData, e))?;
+
+            if let Some(n) = graph::read_header_line(line_no, line, &mut metadata)? {
+                // The body starts on the line after the header.
+                let end = line.as_ptr() as usize - input.as_ptr() as usize + line.len();
+                let offset = match input[end..].iter().position(|&b| b == b'\n') {
+                    Some(newline) => end + newline + 1,
+                    None => input.len(),
+                };
+                header = Some((n, line_no, offset));
+                break;
+            }
+        }
+
+        let (n_transactions, mut line_no, offset) = match header {
+            Some(header) => header,
+            None => {
+                let location = Location::line(count_lines(input) + 1, "");
+                return Err(Error::MissingNumberOfTransactions(location));
+            }
+        };
+
+        let body = &input[offset..];
+        let chunks = split(body, jobs);
+
+        let chunks = thread::scope(|scope| {
+            let handles: Vec<_> = chunks
+                .iter()
+                .map(|chunk| scope.spawn(move || Chunk::parse(chunk)))
+                .collect();
+
+            handles
+                .into_iter()
+                .map(|handle| handle.join().expect("Parsing threads do not panic"))
+                .collect::<Vec<Chunk>>()
+        });
+
+        let max = n_transactions + 1;
+        let mut transactions = Vec::with_capacity(n_transactions);
+
+        // Number and check the transactions in order, reporting the same errors as a
+        // sequential read would.
+        for chunk in chunks {
+            let first_line_no = line_no;
+
+            for (i, t) in chunk.transactions.iter().enumerate() {
+                let locate = |token| {
+                    let (chunk_line_no, line) = chunk.line(i);
+                    let line_no = first_line_no + chunk_line_no;
+                    match token {
+                        Some(token) => Location::token(line_no, &line, token),
+                        None => Location::line(line_no, &line),
+                    }
+                };
+
+                if transactions.len() == n_transactions {
+                    // The number of transactions read so far exceeds the expected number.
+                    return Err(Error::TooManyTransactions(locate(None)));
+                }
+
+                if chunk.first_date_time == Some(i) {
+                    let (chunk_line_no, line) = chunk.line(i);
+                    let line_no = first_line_no + chunk_line_no;
+                    graph::check_date_time(line_no, &line, 2, &mut metadata)?;
+                }
+
+                let id = NonRootId::try_from(transactions.len() + 2).expect("IDs start at 2");
+
+                let left: usize = t.left().into();
+                if left > max {
+                    return Err(Error::InvalidLeft(locate(Some(0)), id, t.left(), max));
+                }
+
+                let right: usize = t.right().into();
+                if right > max {
+                    return Err(Error::InvalidRight(locate(Some(1)), id, t.right(), max));
+                }
+
+                transactions.push(Transaction::new(id, t.left(), t.right(), t.timestamp()));
+            }
+
+            if let Some((chunk_line_no, line, e)) = chunk.error {
+                let line_no = first_line_no + chunk_line_no;
+                let line = String::from_utf8_lossy(line);
+                if transactions.len() == n_transactions {
+                    // The number of transactions read so far exceeds the expected number.
+                    return Err(Error::TooManyTransactions(Location::line(line_no, &line)));
+                }
+
+                return Err(graph::transaction_error(line_no, &line, e));
+            }
+
+            line_no += chunk.n_lines;
+        }
+
+        if transactions.len() < n_transactions {
+            // The number of transactions read is lower than the expected number.
+            let location = Location::line(line_no + 1, "");
+            return Err(Error::TooLittleTransactions(location));
+        }
+
+        // Build the reverse references to a range of IDs per thread. The ranges are
+        // disjoint, so that they are merged without combining the references to any ID.
+        let n_ids = max + 1;
+        let size = n_ids / jobs.max(1) + 1;
+        let reverse = thread::scope(|scope| {
+            let transactions = &transactions;
+            let handles: Vec<_> = (0..n_ids)
+                .step_by(size)
+                .map(|start| {
+                    let ids = start..(start + size).min(n_ids);
+                    scope.spawn(move || ReverseReferences::new(transactions, ids))
+                })
+                .collect();
+
+            handles
+                .into_iter()
+                .map(|handle| handle.join().expect("Indexing threads do not panic"))
+                .collect::<Vec<_>>()
+        });
+
+        let mut graph = Graph::from_parts(transactions, reverse);
+
+        *graph.metadata_mut() = metadata;
+        Ok(graph)
+    }
+}
+
+#[cfg(test)]
+mod parallel_tests {
+    use super::split;
+    use crate::graph::{Error, Graph};
+    use std::convert::TryFrom;
+    use std::io::BufReader;
+
+    #[test]
+    fn split_lines() {
+        let input = b"1 1 120\n2 1 130\n3 2 140\n";
+        assert_eq!(split(input, 1), vec![&input[..]]);
+        assert_eq!(split(input, 2), vec![&input[..16], &input[16..]]);
+        assert_eq!(split(input, 10).len(), 3);
+        assert!(split(b"", 4).is_empty());
+    }
+
+    #[test]
+    fn same_as_sequential() {
+        let input = concat!(
+            "generator: hand\n",
+            "6\n",
+            "1 1 120\n",
+            "# A comment\n",
+            "2 1 130\n",
+            "2 7 140\n",
+            "\n",
+            "3 4 150\n",
+            "1 4 160\n",
+            "5 6 170\n",
+        );
+
+        let expected = Graph::try_from(BufReader::new(input.as_bytes())).unwrap();
+        for jobs in 1..8 {
+            let res = Graph::try_from_parallel(input.as_bytes(), jobs).unwrap();
+            assert_eq!(res, expected);
+            assert_eq!(res.metadata(), expected.metadata());
+        }
+    }
+
+    #[test]
+    fn date_times() {
+        let input = "2\n1 1 120\n2 1 2024-05-01T12:00:03Z\n";
+        for jobs in 1..4 {
+            let res = Graph::try_from_parallel(input.as_bytes(), jobs).unwrap();
+            assert_eq!(res.metadata().time_unit(), Some("ms"));
+        }
+    }
+
+    #[test]
+    fn same_errors_as_sequential() {
+        let inputs = [
+            "3\n1 1 120\n2 1 130\n2 x 140\n",
+            "3\n1 1 120\n2 9 130\n2 x 140\n",
+            "2\n1 1 120\n2 1 130\n2 x 140\n",
+            "2\n1 1 120\n2 1 130\n2 2 140\n",
+            "4\n1 1 120\n2 1 130\n",
+            "3\r\n1 1 120 # First\r\n2 1 130\r\n2 x 140\r\n",
+            "3\n1 1 120\n2 1 é\n",
+            "time-unit: s\n3\n1 1 120\n2 1 130\n2 2 2024-05-01T12:00:00Z\n",
+            "\n",
+        ];
+
+        for input in inputs.iter() {
+            let expected = match Graph::try_from(BufReader::new(input.as_bytes())) {
+                Err(e) => e.render("input.in"),
+                Ok(_) => panic!("Unexpected success"),
+            };
+
+            for jobs in 1..4 {
+                match Graph::try_from_parallel(input.as_bytes(), jobs) {
+                    Err(e @ Error::IO(_)) => panic!("Unexpected IO error: {}", e),
+                    Err(e) => assert_eq!(e.render("input.in"), expected),
+                    Ok(_) => panic!("Unexpected success"),
+                }
+            }
+        }
+    }
+}
@@ -0,0 +1,239 @@
+#![warn(clippy::all)]
+
+//! Statistics about how far back transactions reach when they choose the transactions
+//! they refer to, their parents.
+
+use crate::graph::Graph;
+use crate::id::{Id, NonRootId};
+use crate::stats::{Distribution, Stat, StatResult};
+use crate::time::{Duration, TimeUnit};
+use crate::transaction::Transaction;
+use conv::errors::PosOverflow;
+use std::collections::HashMap as Map;
+use std::convert::TryFrom;
+use std::fmt::{self, Display};
+
+/// How far back the transactions one side refers to are, leaving out references to the
+/// Root. Distances are negative for parents that come after the transaction.
+#[derive(Default, Clone)]
+pub struct Distances {
+    /// The differences between the IDs of the transactions and of their parents.
+    pub ids: Distribution<i128>,
+
+    /// The differences between the timestamps of the transactions and of their parents.
+    pub ages: Distribution<i128>,
+
+    /// The differences between the depths of the transactions and of their parents.
+    pub depths: Distribution<i128>,
+}
+
+/// The result of parent distance statistics.
+pub struct ParentDistancesResult {
+    pub unit: TimeUnit,
+    pub trunk: Distances,
+    pub branch: Distances,
+
+    /// The average ID distance, age and depth difference of the trunks and the branches.
+    means: [[f64; 3]; 2],
+}
+
+impl ParentDistancesResult {
+    fn sides(&self) -> [(&'static str, &Distances, [f64; 3]); 2] {
+        [
+            ("TRUNK", &self.trunk, self.means[0]),
+            ("BRANCH", &self.branch, self.means[1]),
+        ]
+    }
+}
+
+impl Display for ParentDistancesResult {
+    fn fmt(&self, f: &mut fmt::Formatter) -> fmt::Result {
+        // Every i128 is within the range of f64, only its precision may be lost.
+        let duration = |length: Option<i128>| Duration {
+            length: length.map_or(f64::NAN, |length| length as f64),
+            unit: self.unit,
+        };
+
+        for (i, (side, distances, [id, age, depth])) in self.sides().iter().enumerate() {
+            if i > 0 {
+                writeln!(f)?;
+            }
+            writeln!(
+                f,
+                "> {} ID DISTANCE: AVG {:.2}, {}",
+                side, id, distances.ids
+            )?;
+
+            let average = Duration {
+                length: *age,
+                unit: self.unit,
+            };
+            let [min, median, p90, max] = distances.ages.summary().map(duration);
+            writeln!(
+                f,
+                "> {} AGE: AVG {}, MIN {}, MEDIAN {}, P90 {}, MAX {}",
+                side, average, min, median, p90, max
+            )?;
+
+            write!(
+                f,
+                "> {} DEPTH DIFFERENCE: AVG {:.2}, {}",
+                side, depth, distances.depths
+            )?;
+        }
+
+        Ok(())
+    }
+}
+
+impl StatResult for ParentDistancesResult {
+    fn values(&self) -> Vec<(&'static str, f64)> {
+        let max = |distribution: &Distribution<i128>| {
+            distribution
+                .quantile(1.0)
+                .map_or(f64::NAN, |max| max as f64)
+        };
+        let [trunk, branch] = self.means;
+        vec![
+            ("AVG TRUNK ID DISTANCE", trunk[0]),
+            ("MAX TRUNK ID DISTANCE", max(&self.trunk.ids)),
+            ("AVG TRUNK AGE", trunk[1]),
+            ("MAX TRUNK AGE", max(&self.trunk.ages)),
+            ("AVG TRUNK DEPTH DIFFERENCE", trunk[2]),
+            ("MAX TRUNK DEPTH DIFFERENCE", max(&self.trunk.depths)),
+            ("AVG BRANCH ID DISTANCE", branch[0]),
+            ("MAX BRANCH ID DISTANCE", max(&self.branch.ids)),
+            ("AVG BRANCH AGE", branch[1]),
+            ("MAX BRANCH AGE", max(&self.branch.ages)),
+            ("AVG BRANCH DEPTH DIFFERENCE", branch[2]),
+            ("MAX BRANCH DEPTH DIFFERENCE", max(&self.branch.depths)),
+        ]
+    }
+}
+
+/// The accumulator for the distances between transactions and their parents.
+pub struct ParentDistances<'a> {
+    /// Keep a reference to the graph to look up parents and call depth().
+    graph: &'a Graph,
+
+    /// The unit of the timestamps.
+    unit: TimeUnit,
+
+    /// The depth calculation cache.
+    cache: Map<NonRootId, usize>,
+
+    /// The distances to the transactions referred to by trunks.
+    trunk: Distances,
+
+    /// The distances to the transactions referred to by branches.
+    branch: Distances,
+}
+
+impl<'a> ParentDistances<'a> {
+    /// The statistics in the time unit of the graph.
+    pub fn new(graph: &'a Graph) -> Self {
+        Self::with_unit(graph, TimeUnit::of(graph))
+    }
+
+    pub fn with_unit(graph: &'a Graph, unit: TimeUnit) -> Self {
+        Self {
+            graph,
+            unit,
+            cache: Map::with_capacity(graph.len()),
+            trunk: Distances::default(),
+            branch: Distances::default(),
+        }
+    }
+}
+
+impl<'a> Stat<'a> for ParentDistances<'a> {
+    fn accumulate(&mut self, transaction: &Transaction) {
+        let depth = self.graph.depth(transaction.id(), &mut self.cache);
+        let sides = [
+            (transaction.left(), &mut self.trunk),
+            (transaction.right(), &mut self.branch),
+        ];
+
+        for (reference, distances) in sides {
+            if let Id::Transaction(parent) = reference {
+                let parent_depth = self.graph.depth(parent, &mut self.cache);
+                let parent_timestamp = self.graph[parent].timestamp();
+
+                let id_distance = signed(transaction.id().into()) - signed(parent.into());
+                distances.ids.add(id_distance, 1);
+                let age = i128::from(transaction.timestamp()) - i128::from(parent_timestamp);
+                distances.ages.add(age, 1);
+                distances
+                    .depths
+                    .add(signed(depth) - signed(parent_depth), 1);
+            }
+        }
+    }
+
+    fn result(&self, _n_transactions: f64) -> Result<Box<dyn StatResult>, PosOverflow<usize>> {
+        let means = |distances: &Distances| -> Result<[f64; 3], PosOverflow<usize>> {
+            Ok([
+                distances.ids.mean_and_deviation()?.0,
+                distances.ages.mean_and_deviation()?.0,
+                distances.depths.mean_and_deviation()?.0,
+            ])
+        };
+
+        Ok(Box::new(ParentDistancesResult {
+            unit: self.unit,
+            trunk: self.trunk.clone(),
+            branch: self.branch.clone(),
+            means: [means(&self.trunk)?, means(&self.branch)?],
+        }))
+    }
+}
+
+/// Convert an ID or a depth so that it can be subtracted from another one.
+fn signed(value: usize) -> i128 {
+    i128::try_from(value).expect("Counts fit in 128 bits")
+}
+
+#[cfg(test)]
+mod parents_tests {
+    use super::ParentDistances;
+    use crate::graph::Graph;
+    use crate::stats::{self, Stat};
+    use std::convert::TryFrom;
+    use std::io::BufReader;
+
+    fn parent_distances(input: &str) -> String {
+        let graph = Graph::try_from(BufReader::new(input.as_bytes())).unwrap();
+        let stats: Vec<Box<dyn Stat>> = vec![Box::new(ParentDistances::new(&graph))];
+        stats::accumulate(&graph, stats).unwrap()[0].to_string()
+    }
+
+    #[test]
+    fn distances() {
+        let input = "5\n1 1 0\n1 2 0\n2 2 1\n3 6 3\n3 3 2\n";
+        assert_eq!(
+            parent_distances(input),
+            "> TRUNK ID DISTANCE: AVG 2.33, MIN 2, MEDIAN 2, P90 3, MAX 3\n\
+             > TRUNK AGE: AVG 2.00 ticks, MIN 1.00 ticks, MEDIAN 2.00 ticks, P90 3.00 ticks, \
+             MAX 3.00 ticks\n\
+             > TRUNK DEPTH DIFFERENCE: AVG 1.00, MIN 1, MEDIAN 1, P90 1, MAX 1\n\
+             > BRANCH ID DISTANCE: AVG 1.25, MIN -1, MEDIAN 1, P90 3, MAX 3\n\
+             > BRANCH AGE: AVG 1.00 ticks, MIN 0.00 ticks, MEDIAN 1.00 ticks, P90 2.00 ticks, \
+             MAX 2.00 ticks\n\
+             > BRANCH DEPTH DIFFERENCE: AVG 0.50, MIN 0, MEDIAN 0, P90 1, MAX 1"
+        );
+    }
+
+    #[test]
+    fn root_references() {
+        let input = "time-unit: ms\n2\n1 1 1000\n1 1 3000\n";
+        assert_eq!(
+            parent_distances(input),
+            "> TRUNK ID DISTANCE: AVG NaN, MIN NaN, MEDIAN NaN, P90 NaN, MAX NaN\n\
+             > TRUNK AGE: AVG NaN s, MIN NaN s, MEDIAN NaN s, P90 NaN s, MAX NaN s\n\
+             > TRUNK DEPTH DIFFERENCE: AVG NaN, MIN NaN, MEDIAN NaN, P90 NaN, MAX NaN\n\
+             > BRANCH ID DISTANCE: AVG NaN, MIN NaN, MEDIAN NaN, P90 NaN, MAX NaN\n\
+             > BRANCH AGE: AVG NaN s, MIN NaN s, MEDIAN NaN s, P90 NaN s, MAX NaN s\n\
+             > BRANCH DEPTH DIFFERENCE: AVG NaN, MIN NaN, MEDIAN NaN, P90 NaN, MAX NaN"
+        );
+    }
+}
@@ -0,0 +1,147 @@
+#![warn(clippy::all)]
+
+//! Pruning of old history, as done by nodes that only keep recent transactions in memory.
+
+use crate::diff::StatChange;
+use crate::graph::Graph;
+use crate::id::{Id, NonRootId};
+use crate::stats::{self, Depths, InReferences, Stat, StatResult};
+use crate::time::Timestamp;
+use crate::transaction::Transaction;
+use conv::errors::PosOverflow;
+use std::collections::HashSet as Set;
+
+/// A `Graph` out of which old transactions were pruned.
+pub struct Pruned {
+    /// The remaining transactions, renumbered.
+    pub graph: Graph,
+
+    /// The number of transactions that were removed.
+    pub n_pruned: usize,
+
+    /// The number of solid entry points, the pruned transactions that were kept because
+    /// remaining transactions refer to them.
+    pub n_entry_points: usize,
+}
+
+impl Pruned {
+    /// How the depth and reverse reference statistics shifted from the original `Graph`.
+    pub fn stat_changes(&self, original: &Graph) -> Result<Vec<StatChange>, PosOverflow<usize>> {
+        let old = history_results(original)?;
+        let new = history_results(&self.graph)?;
+        Ok(StatChange::between(&old, &new))
+    }
+}
+
+/// The results of the statistics that depend on the history of transactions.
+fn history_results(graph: &Graph) -> Result<Vec<Box<dyn StatResult>>, PosOverflow<usize>> {
+    let stats: Vec<Box<dyn Stat>> = vec![
+        Box::new(Depths::new(graph)),
+        Box::new(InReferences::new(graph)),
+    ];
+
+    stats::accumulate(graph, stats)
+}
+
+impl Graph {
+    /// Remove the transactions older than `before_timestamp`. References from the remaining
+    /// transactions into the pruned region point to the Root instead, and the remaining
+    /// transactions are renumbered.
+    pub fn prune(&self, before_timestamp: Timestamp) -> Pruned {
+        let kept = self.recent(before_timestamp);
+        let graph = self.subgraph(&kept, &Set::new());
+
+        Pruned {
+            n_pruned: self.len() - graph.len(),
+            n_entry_points: 0,
+            graph,
+        }
+    }
+
+    /// Remove the transactions older than `before_timestamp` like `Graph::prune`, but keep
+    /// the pruned transactions that remaining transactions refer to as solid entry points.
+    /// Entry points refer to the Root, since their own history is pruned.
+    pub fn prune_to_entry_points(&self, before_timestamp: Timestamp) -> Pruned {
+        let mut kept = self.recent(before_timestamp);
+
+        let entry_points: Set<NonRootId> = self
+            .transactions()
+            .filter(|t| kept.contains(&t.id()))
+            .flat_map(|t| vec![t.left(), t.right()])
+            .filter_map(|id| match id {
+                Id::Transaction(id) if !kept.contains(&id) => Some(id),
+                _ => None,
+            })
+            .collect();
+
+        kept.extend(&entry_points);
+        let graph = self.subgraph(&kept, &entry_points);
+
+        Pruned {
+            n_pruned: self.len() - graph.len(),
+            n_entry_points: entry_points.len(),
+            graph,
+        }
+    }
+
+    fn recent(&self, before_timestamp: Timestamp) -> Set<NonRootId> {
+        self.transactions()
+            .filter(|t| t.timestamp() >= before_timestamp)
+            .map(Transaction::id)
+            .collect()
+    }
+}
+
+#[cfg(test)]
+mod prune_tests {
+    use crate::graph::Graph;
+    use std::convert::TryFrom;
+    use std::io::BufReader;
+
+    // Transaction 5 refers to transaction 7 that comes after it.
+    const INPUT: &str = "6\n1 1 100\n2 1 110\n3 2 120\n7 3 130\n4 3 140\n4 6 150\n";
+
+    fn write(graph: &Graph) -> String {
+        let mut output = Vec::new();
+        graph.write(&mut output).unwrap();
+        String::from_utf8(output).unwrap()
+    }
+
+    #[test]
+    fn prune() {
+        let graph = Graph::try_from(BufReader::new(INPUT.as_bytes())).unwrap();
+        let pruned = graph.prune(130);
+        assert_eq!(pruned.n_pruned, 3);
+        assert_eq!(pruned.n_entry_points, 0);
+        assert_eq!(write(&pruned.graph), "3\n4 1 130\n1 1 140\n1 3 150\n");
+        assert_eq!(pruned.graph.is_connected_acyclic(), Some(true));
+
+        let changes = pruned.stat_changes(&graph).unwrap();
+        let changes: Vec<String> = changes.iter().map(ToString::to_string).collect();
+        assert_eq!(
+            changes,
+            vec![
+                "> AVG DAG DEPTH: 1.57 -> 0.75 (-0.82)",
+                "> AVG TXS PER DEPTH: 2.00 -> 3.00 (+1.00)",
+                "> AVG REF: 1.71 -> 1.50 (-0.21)",
+            ]
+        );
+    }
+
+    #[test]
+    fn prune_to_entry_points() {
+        let graph = Graph::try_from(BufReader::new(INPUT.as_bytes())).unwrap();
+        let pruned = graph.prune_to_entry_points(130);
+        assert_eq!(pruned.n_pruned, 1);
+        assert_eq!(pruned.n_entry_points, 2);
+        assert_eq!(
+            write(&pruned.graph),
+            "5\n1 1 110\n1 1 120\n6 2 130\n3 2 140\n3 5 150\n"
+        );
+        assert_eq!(pruned.graph.is_connected_acyclic(), Some(true));
+
+        let pruned = graph.prune_to_entry_points(0);
+        assert_eq!(pruned.n_pruned, 0);
+        assert_eq!(pruned.graph, graph);
+    }
+}
@@ -0,0 +1,222 @@
+#![warn(clippy::all)]
+
+//! Detection of redundant references, which add no approval coverage since the transaction
+//! they point to is already approved through the other reference, and the transitive
+//! reduction of a `Graph` without them.
+
+use crate::graph::Graph;
+use crate::id::{Id, NonRootId};
+use crate::stats::{Stat, StatResult};
+use crate::transaction::Transaction;
+use conv::{errors::PosOverflow, ValueFrom};
+use std::collections::HashMap as Map;
+use std::collections::HashSet as Set;
+use std::fmt::{self, Display};
+
+/// Which reference of a transaction is redundant.
+#[derive(Clone, Copy, PartialEq, Eq, Debug)]
+pub enum Redundant {
+    /// The left reference points to the transaction the right one points to, or to one of
+    /// the transactions it refers to, directly or not.
+    Left,
+
+    /// The right reference points to one of the transactions the left one refers to,
+    /// directly or not.
+    Right,
+}
+
+impl Graph {
+    /// The redundant reference of a transaction, if any. The Root is referred to by every
+    /// transaction, directly or not, so a reference to the Root is redundant when the other
+    /// reference points to a transaction. `heights` is a `Graph::height` cache. Assumes the
+    /// graph is acyclic.
+    pub fn redundant_reference(
+        &self,
+        transaction: &Transaction,
+        heights: &mut Map<NonRootId, usize>,
+    ) -> Option<Redundant> {
+        let (left, right) = (transaction.left(), transaction.right());
+
+        if self.reaches(right, left, heights) {
+            Some(Redundant::Left)
+        } else if self.reaches(left, right, heights) {
+            Some(Redundant::Right)
+        } else {
+            None
+        }
+    }
+
+    /// The graph without redundant references: each redundant reference is redirected to
+    /// the transaction the other reference points to, so that the distinct references form
+    /// the transitive reduction of the graph. Transactions keep their IDs and timestamps,
+    /// and the metadata is kept. Assumes the graph is acyclic.
+    pub fn transitive_reduction(&self) -> Graph {
+        let mut heights = Map::with_capacity(self.len());
+        let mut graph = Graph::with_capacity(self.len());
+
+        for t in self.transactions() {
+            let (left, right) = match self.redundant_reference(t, &mut heights) {
+                Some(Redundant::Left) => (t.right(), t.right()),
+                Some(Redundant::Right) => (t.left(), t.left()),
+                None => (t.left(), t.right()),
+            };
+            graph.push(Transaction::new(t.id(), left, right, t.timestamp()));
+        }
+
+        *graph.metadata_mut() = self.metadata().clone();
+        graph
+    }
+
+    /// Whether `target` is `from` or one of the transactions it refers to, directly or not.
+    /// Only transactions with a longer path to the Root than `target` can lead to it, which
+    /// bounds the search.
+    fn reaches(&self, from: Id, target: Id, heights: &mut Map<NonRootId, usize>) -> bool {
+        let (from, target) = match (from, target) {
+            _ if from == target => return true,
+            (_, Id::Root) => return true,
+            (Id::Root, _) => return false,
+            (Id::Transaction(from), Id::Transaction(target)) => (from, target),
+        };
+
+        let target_height = self.height(target, heights);
+        let mut visited = Set::new();
+        let mut stack = vec![from];
+
+        while let Some(id) = stack.pop() {
+            if id == target {
+                return true;
+            }
+            if !visited.insert(id) || self.height(id, heights) <= target_height {
+                continue;
+            }
+
+            let t = &self[id];
+            for parent in [t.left(), t.right()] {
+                if let Id::Transaction(parent) = parent {
+                    stack.push(parent);
+                }
+            }
+        }
+
+        false
+    }
+}
+
+/// The result of redundant reference statistics.
+pub struct RedundancyResult {
+    pub(crate) redundant_references: f64,
+    pub(crate) redundant_fraction: f64,
+}
+
+impl Display for RedundancyResult {
+    fn fmt(&self, f: &mut fmt::Formatter) -> fmt::Result {
+        writeln!(f, "> REDUNDANT REFERENCES: {}", self.redundant_references)?;
+        write!(f, "> REDUNDANT FRACTION: {:.2}", self.redundant_fraction)
+    }
+}
+
+impl StatResult for RedundancyResult {
+    fn values(&self) -> Vec<(&'static str, f64)> {
+        vec![
+            ("REDUNDANT REFERENCES", self.redundant_references),
+            ("REDUNDANT FRACTION", self.redundant_fraction),
+        ]
+    }
+}
+
+/// The accumulator for the number of redundant references.
+pub struct Redundancy<'a> {
+    /// Keep a reference to the graph to follow references.
+    graph: &'a Graph,
+
+    /// The height calculation cache, which bounds the searches for redundant references.
+    heights: Map<NonRootId, usize>,
+
+    /// The number of redundant references.
+    redundant_references: usize,
+}
+
+impl<'a> Redundancy<'a> {
+    pub fn new(graph: &'a Graph) -> Self {
+        Self {
+            graph,
+            heights: Map::with_capacity(graph.len()),
+            redundant_references: 0,
+        }
+    }
+}
+
+impl<'a> Stat<'a> for Redundancy<'a> {
+    fn accumulate(&mut self, transaction: &Transaction) {
+        if self
+            .graph
+            .redundant_reference(transaction, &mut self.heights)
+            .is_some()
+        {
+            self.redundant_references += 1;
+        }
+    }
+
+    fn result(&self, n_transactions: f64) -> Result<Box<dyn StatResult>, PosOverflow<usize>> {
+        let redundant_references = f64::value_from(self.redundant_references)?;
+        Ok(Box::new(RedundancyResult {
+            redundant_references,
+            redundant_fraction: redundant_references / (2.0 * n_transactions),
+        }))
+    }
+}
+
+#[cfg(test)]
+mod reduction_tests {
+    use super::{Redundancy, Redundant};
+    use crate::graph::test_graph;
+    use crate::stats::{self, Stat};
+    use std::collections::HashMap as Map;
+
+    #[test]
+    fn redundant_references() {
+        // Both references of transaction 2 point to the Root. Transaction 4 refers to
+        // transaction 2 and to transaction 3, which already refers to transaction 2.
+        // Transaction 5 refers to transaction 4 and, through it, to transaction 3 again.
+        let tangle = test_graph("5\n1 1 0\n2 1 1\n2 3 2\n4 3 3\n1 4 4\n");
+        let mut heights = Map::new();
+        let redundant: Vec<Option<Redundant>> = tangle
+            .transactions()
+            .map(|t| tangle.redundant_reference(t, &mut heights))
+            .collect();
+        assert_eq!(
+            redundant,
+            vec![
+                Some(Redundant::Left),
+                Some(Redundant::Right),
+                Some(Redundant::Left),
+                Some(Redundant::Right),
+                Some(Redundant::Left)
+            ]
+        );
+
+        let stats: Vec<Box<dyn Stat>> = vec![Box::new(Redundancy::new(&tangle))];
+        let results = stats::accumulate(&tangle, stats).unwrap();
+        assert_eq!(
+            results[0].to_string(),
+            "> REDUNDANT REFERENCES: 5\n> REDUNDANT FRACTION: 0.50"
+        );
+
+        // Transactions referring to two unrelated transactions waste nothing.
+        let tangle = test_graph("3\n1 1 0\n1 1 0\n2 3 1\n");
+        let mut heights = Map::new();
+        let t = tangle.transactions().last().unwrap();
+        assert_eq!(tangle.redundant_reference(t, &mut heights), None);
+    }
+
+    #[test]
+    fn transitive_reduction() {
+        let tangle = test_graph("source: test\n5\n1 1 0\n2 1 1\n2 3 2\n4 3 3\n1 4 4\n");
+        let mut output = Vec::new();
+        tangle.transitive_reduction().write(&mut output).unwrap();
+        assert_eq!(
+            String::from_utf8(output).unwrap(),
+            "source: test\n5\n1 1 0\n2 2 1\n3 3 2\n4 4 3\n4 4 4\n"
+        );
+    }
+}
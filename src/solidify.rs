@@ -0,0 +1,201 @@
+#![warn(clippy::all)]
+
+//! Ordering of transactions that arrive before the transactions they refer to.
+
+use crate::graph::Graph;
+use crate::id::Id;
+use crate::names::{self, Names};
+use crate::time::Timestamp;
+use crate::transaction::{NamedTransaction, Transaction};
+use derive_more::Display;
+use std::collections::{HashMap as Map, HashSet as Set};
+
+/// A transaction that is waiting for one of its references to become known.
+#[derive(PartialEq, Eq, Debug, Display)]
+#[display(fmt = "{} <{}, {}, {}>", id, left, right, timestamp)]
+pub struct Unsolid {
+    pub id: String,
+    pub left: String,
+    pub right: String,
+    pub timestamp: Timestamp,
+}
+
+impl Unsolid {
+    /// The identifier of the reference this transaction is waiting for.
+    pub fn missing<'a>(&'a self, names: &Names) -> &'a str {
+        if names.get(&self.left).is_none() {
+            &self.left
+        } else {
+            &self.right
+        }
+    }
+}
+
+impl From<NamedTransaction<'_>> for Unsolid {
+    fn from(t: NamedTransaction) -> Self {
+        Self {
+            id: t.id.into(),
+            left: t.left.into(),
+            right: t.right.into(),
+            timestamp: t.timestamp,
+        }
+    }
+}
+
+/// Builds a `Graph` in topological order out of transactions inserted in any order.
+/// Transactions are attached to the `Graph` once both of their references are known
+/// ("solid"), otherwise they are buffered until the missing reference is attached.
+pub struct Solidifier {
+    /// The graph of solid transactions.
+    graph: Graph,
+
+    /// The identifiers of the solid transactions.
+    names: Names,
+
+    /// The buffered transactions, keyed by the identifier of the reference they are
+    /// waiting for.
+    waiting: Map<String, Vec<Unsolid>>,
+
+    /// The identifiers of the buffered transactions, to detect duplicates early.
+    buffered: Set<String>,
+}
+
+impl Solidifier {
+    pub fn with_capacity(root: &str, cap: usize) -> Self {
+        Self {
+            graph: Graph::with_capacity(cap),
+            names: Names::new(root),
+            waiting: Map::new(),
+            buffered: Set::new(),
+        }
+    }
+
+    /// The number of transactions inserted so far, solid or not.
+    pub fn n_inserted(&self) -> usize {
+        self.graph.len() + self.buffered.len()
+    }
+
+    /// Insert a transaction, attaching it and any transactions that were waiting for it
+    /// if its references are known.
+    pub fn insert(&mut self, transaction: NamedTransaction) -> Result<(), names::Error> {
+        if self.buffered.contains(transaction.id) {
+            return Err(names::Error::Duplicate(transaction.id.into()));
+        }
+
+        // Interned transactions and the Root are rejected by the interning itself, but
+        // only once the transaction becomes solid. Check for them upfront.
+        match self.names.get(transaction.id) {
+            Some(Id::Root) => return Err(names::Error::Reserved(transaction.id.into())),
+            Some(Id::Transaction(_)) => return Err(names::Error::Duplicate(transaction.id.into())),
+            None => {}
+        }
+
+        let mut ready = vec![Unsolid::from(transaction)];
+
+        while let Some(t) = ready.pop() {
+            let left = self.names.get(&t.left);
+            let right = self.names.get(&t.right);
+
+            match (left, right) {
+                (Some(left), Some(right)) => {
+                    // The transaction is solid. Attach it and release the transactions that
+                    // were waiting for it, which might now be solid themselves.
+                    self.buffered.remove(&t.id);
+                    let id = self.names.intern(&t.id)?;
+                    self.graph
+                        .push(Transaction::new(id, left, right, t.timestamp));
+
+                    if let Some(waiting) = self.waiting.remove(&t.id) {
+                        ready.extend(waiting);
+                    }
+                }
+                (None, _) => self.wait(t.left.clone(), t),
+                (_, None) => self.wait(t.right.clone(), t),
+            }
+        }
+
+        Ok(())
+    }
+
+    fn wait(&mut self, missing: String, transaction: Unsolid) {
+        self.buffered.insert(transaction.id.clone());
+        self.waiting.entry(missing).or_default().push(transaction);
+    }
+
+    /// Stop accepting transactions, returning the `Graph` of solid transactions and the
+    /// transactions that never became solid.
+    pub fn finish(self) -> (Graph, Vec<Unsolid>) {
+        let mut graph = self.graph;
+        graph.set_names(self.names);
+
+        let mut unsolid: Vec<Unsolid> = self.waiting.into_values().flatten().collect();
+        unsolid.sort_by(|a, b| a.id.cmp(&b.id));
+
+        (graph, unsolid)
+    }
+}
+
+#[cfg(test)]
+mod solidify_tests {
+    use super::{Solidifier, Unsolid};
+    use crate::names;
+    use crate::transaction::NamedTransaction;
+    use std::convert::TryFrom;
+
+    fn insert(solidifier: &mut Solidifier, line: &str) -> Result<(), names::Error> {
+        solidifier.insert(NamedTransaction::try_from(line).unwrap())
+    }
+
+    #[test]
+    fn topological_order() {
+        let mut solidifier = Solidifier::with_capacity("g", 3);
+        insert(&mut solidifier, "c a b 30").unwrap();
+        insert(&mut solidifier, "b a g 20").unwrap();
+        assert_eq!(solidifier.n_inserted(), 2);
+        insert(&mut solidifier, "a g g 10").unwrap();
+
+        let (graph, unsolid) = solidifier.finish();
+        assert!(unsolid.is_empty());
+
+        let names: Vec<String> = graph
+            .transactions()
+            .map(|t| graph.name(t.id().into()))
+            .collect();
+        assert_eq!(names, vec!["a", "b", "c"]);
+        assert_eq!(graph.is_connected_acyclic(), Some(true));
+    }
+
+    #[test]
+    fn never_solid() {
+        let mut solidifier = Solidifier::with_capacity("g", 4);
+        insert(&mut solidifier, "a g g 10").unwrap();
+        insert(&mut solidifier, "b a x 20").unwrap();
+        insert(&mut solidifier, "c b a 30").unwrap();
+
+        let (graph, unsolid) = solidifier.finish();
+        assert_eq!(graph.len(), 1);
+        assert_eq!(unsolid.len(), 2);
+        assert_eq!(
+            unsolid[0],
+            Unsolid::from(NamedTransaction::try_from("b a x 20").unwrap())
+        );
+        assert_eq!(unsolid[0].missing(graph.names().unwrap()), "x");
+        assert_eq!(unsolid[1].missing(graph.names().unwrap()), "b");
+    }
+
+    #[test]
+    fn duplicates() {
+        let mut solidifier = Solidifier::with_capacity("g", 4);
+        insert(&mut solidifier, "a g g 10").unwrap();
+        insert(&mut solidifier, "b x g 20").unwrap();
+
+        let err = names::Error::Duplicate(String::from("a"));
+        assert_eq!(insert(&mut solidifier, "a g g 10"), Err(err));
+
+        let err = names::Error::Duplicate(String::from("b"));
+        assert_eq!(insert(&mut solidifier, "b g g 10"), Err(err));
+
+        let err = names::Error::Reserved(String::from("g"));
+        assert_eq!(insert(&mut solidifier, "g a a 10"), Err(err));
+    }
+}
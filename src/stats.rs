@@ -2,12 +2,14 @@
 
 //! Structures that collect statistics about `Graph`s.
 
-use crate::graph::{Graph, References};
+use crate::graph::{DepthPath, Graph, References};
 use crate::id::Id;
 use crate::id::NonRootId;
+use crate::time::{Duration, Rate, TimeUnit, Timestamp};
 use crate::transaction::Transaction;
 use conv::{errors::PosOverflow, ValueFrom};
-use std::collections::{HashMap as Map, HashSet as Set};
+use std::collections::{BTreeMap, HashMap as Map, HashSet as Set};
+use std::convert::TryFrom;
 use std::fmt::{self, Display};
 
 /// A statistic about the graph.
@@ -18,13 +20,54 @@ pub trait Stat<'a> {
     /// When accumulation is over, this function can be called to get back a printable
     /// representation of the statistic. The errors may be caused due to invalid
     /// conversions from usizes to f64s used for divisions.
-    fn result(&self, n_transactions: f64) -> Result<Box<dyn Display>, PosOverflow<usize>>;
+    fn result(&self, n_transactions: f64) -> Result<Box<dyn StatResult>, PosOverflow<usize>>;
+}
+
+/// The printable result of a statistic, whose values can also be compared.
+pub trait StatResult: Display {
+    /// The names and values of the result, in the order they are printed.
+    fn values(&self) -> Vec<(&'static str, f64)>;
+}
+
+/// Compute the results of the default statistics about a graph.
+pub fn results(graph: &Graph) -> Result<Vec<Box<dyn StatResult>>, PosOverflow<usize>> {
+    accumulate(graph, defaults(graph))
+}
+
+/// The default statistics about a graph, to which optional ones can be added before they
+/// are accumulated.
+pub fn defaults(graph: &Graph) -> Vec<Box<dyn Stat<'_> + '_>> {
+    vec![
+        Box::new(Depths::new(graph)),
+        Box::new(InReferences::new(graph)),
+        Box::new(Throughput::new(graph)),
+        Box::new(Timestamps::new(graph)),
+    ]
+}
+
+/// Accumulate statistics over all the transactions of a graph and compute their results.
+pub fn accumulate<'a>(
+    graph: &Graph,
+    mut stats: Vec<Box<dyn Stat<'a> + 'a>>,
+) -> Result<Vec<Box<dyn StatResult>>, PosOverflow<usize>> {
+    let n_transactions = f64::value_from(graph.len())?;
+
+    for transaction in graph.transactions() {
+        for stat in &mut stats {
+            stat.accumulate(transaction);
+        }
+    }
+
+    stats
+        .iter()
+        .map(|stat| stat.result(n_transactions))
+        .collect()
 }
 
 /// The result of depth statistics.
 pub struct DepthsResult {
-    average_depth: f64,
-    average_txs_per_depth: f64,
+    pub(crate) average_depth: f64,
+    pub(crate) average_txs_per_depth: f64,
 }
 
 impl Display for DepthsResult {
@@ -34,11 +77,23 @@ impl Display for DepthsResult {
     }
 }
 
+impl StatResult for DepthsResult {
+    fn values(&self) -> Vec<(&'static str, f64)> {
+        vec![
+            ("AVG DAG DEPTH", self.average_depth),
+            ("AVG TXS PER DEPTH", self.average_txs_per_depth),
+        ]
+    }
+}
+
 /// The accumulator for statistics related to transaction depths.
 pub struct Depths<'a> {
     /// Keep a reference to the graph so that we can call depth().
     graph: &'a Graph,
 
+    /// Whether depths are the lengths of the shortest or of the longest paths to the Root.
+    path: DepthPath,
+
     /// The depth calculation cache to avoid recomputing depths that have already been
     /// computed.
     cache: Map<NonRootId, usize>,
@@ -52,8 +107,14 @@ pub struct Depths<'a> {
 
 impl<'a> Depths<'a> {
     pub fn new(graph: &'a Graph) -> Self {
+        Self::with_path(graph, DepthPath::Shortest)
+    }
+
+    /// The depth statistics along the shortest or the longest paths to the Root.
+    pub fn with_path(graph: &'a Graph, path: DepthPath) -> Self {
         Self {
             graph,
+            path,
             cache: Map::with_capacity(graph.len()),
             sum_of_depths: 0,
             unique_depths: Set::with_capacity(graph.len()),
@@ -63,12 +124,14 @@ impl<'a> Depths<'a> {
 
 impl<'a> Stat<'a> for Depths<'a> {
     fn accumulate(&mut self, transaction: &Transaction) {
-        let depth = self.graph.depth(transaction.id(), &mut self.cache);
+        let depth = self
+            .graph
+            .depth_along(transaction.id(), self.path, &mut self.cache);
         self.sum_of_depths += depth;
         self.unique_depths.insert(depth);
     }
 
-    fn result(&self, n_transactions: f64) -> Result<Box<dyn Display>, PosOverflow<usize>> {
+    fn result(&self, n_transactions: f64) -> Result<Box<dyn StatResult>, PosOverflow<usize>> {
         let n_unique_depths = f64::value_from(self.unique_depths.len())?;
         let sum_of_depths = f64::value_from(self.sum_of_depths)?;
         Ok(Box::new(DepthsResult {
@@ -80,7 +143,7 @@ impl<'a> Stat<'a> for Depths<'a> {
 
 /// The result of reverse reference statistics.
 pub struct InReferencesResult {
-    average_references: f64,
+    pub(crate) average_references: f64,
 }
 
 impl Display for InReferencesResult {
@@ -89,6 +152,12 @@ impl Display for InReferencesResult {
     }
 }
 
+impl StatResult for InReferencesResult {
+    fn values(&self) -> Vec<(&'static str, f64)> {
+        vec![("AVG REF", self.average_references)]
+    }
+}
+
 /// The accumulator for statistics related to reverse transaction references.
 pub struct InReferences<'a> {
     /// Keep a reference to the graph to be able to access the list of reverse references.
@@ -132,7 +201,7 @@ impl<'a> Stat<'a> for InReferences<'a> {
         ));
     }
 
-    fn result(&self, n_transactions: f64) -> Result<Box<dyn Display>, PosOverflow<usize>> {
+    fn result(&self, n_transactions: f64) -> Result<Box<dyn StatResult>, PosOverflow<usize>> {
         let total_references = f64::value_from(self.total_references.unwrap_or(0))?;
         Ok(Box::new(InReferencesResult {
             average_references: total_references / (n_transactions + 1.0),
@@ -140,40 +209,194 @@ impl<'a> Stat<'a> for InReferences<'a> {
     }
 }
 
-/// The result of the statistic related to time units.
-pub struct TimeUnitsResult {
-    average_txs_per_time_unit: f64,
+/// The result of height statistics.
+pub struct HeightsResult {
+    pub(crate) average_height: f64,
+    pub(crate) heights: Distribution,
+    pub(crate) average_gap: f64,
+    pub(crate) gaps: Distribution,
 }
 
-impl Display for TimeUnitsResult {
+impl Display for HeightsResult {
     fn fmt(&self, f: &mut fmt::Formatter) -> fmt::Result {
-        write!(
+        writeln!(f, "> AVG HEIGHT (INCL. ROOT): {:.2}", self.average_height)?;
+        writeln!(f, "> HEIGHTS: {}", self.heights)?;
+        writeln!(
             f,
-            "> AVG TXS PER TIME UNIT: {:.2}",
-            self.average_txs_per_time_unit,
-        )
+            "> AVG HEIGHT - DEPTH (INCL. ROOT): {:.2}",
+            self.average_gap
+        )?;
+        write!(f, "> HEIGHT - DEPTH: {}", self.gaps)
+    }
+}
+
+impl StatResult for HeightsResult {
+    fn values(&self) -> Vec<(&'static str, f64)> {
+        // Path lengths are at most the number of transactions, so they fit.
+        let max = |distribution: &Distribution| {
+            let max = distribution.quantile(1.0);
+            max.map_or(f64::NAN, |max| span_to_f64(max).unwrap_or(f64::NAN))
+        };
+        vec![
+            ("AVG HEIGHT (INCL. ROOT)", self.average_height),
+            ("MAX HEIGHT", max(&self.heights)),
+            ("AVG HEIGHT - DEPTH (INCL. ROOT)", self.average_gap),
+            ("MAX HEIGHT - DEPTH", max(&self.gaps)),
+        ]
+    }
+}
+
+/// The accumulator for statistics related to transaction heights, the lengths of the
+/// longest paths to the Root, and to how much longer they are than the shortest ones.
+pub struct Heights<'a> {
+    /// Keep a reference to the graph so that we can call height() and depth().
+    graph: &'a Graph,
+
+    /// The height calculation cache.
+    heights_cache: Map<NonRootId, usize>,
+
+    /// The depth calculation cache.
+    depths_cache: Map<NonRootId, usize>,
+
+    /// The sum of all transaction heights.
+    sum_of_heights: usize,
+
+    /// The sum of the differences between the heights and the depths of the transactions.
+    sum_of_gaps: usize,
+
+    /// The heights of the transactions.
+    heights: Distribution,
+
+    /// The differences between the heights and the depths of the transactions.
+    gaps: Distribution,
+}
+
+impl<'a> Heights<'a> {
+    pub fn new(graph: &'a Graph) -> Self {
+        Self {
+            graph,
+            heights_cache: Map::with_capacity(graph.len()),
+            depths_cache: Map::with_capacity(graph.len()),
+            sum_of_heights: 0,
+            sum_of_gaps: 0,
+            heights: Distribution::default(),
+            gaps: Distribution::default(),
+        }
+    }
+}
+
+impl<'a> Stat<'a> for Heights<'a> {
+    fn accumulate(&mut self, transaction: &Transaction) {
+        let height = self.graph.height(transaction.id(), &mut self.heights_cache);
+        let depth = self.graph.depth(transaction.id(), &mut self.depths_cache);
+        self.sum_of_heights += height;
+        self.sum_of_gaps += height - depth;
+
+        let as_u64 = |length| u64::try_from(length).expect("Path lengths fit in 64 bits");
+        self.heights.add(as_u64(height), 1);
+        self.gaps.add(as_u64(height - depth), 1);
+    }
+
+    fn result(&self, n_transactions: f64) -> Result<Box<dyn StatResult>, PosOverflow<usize>> {
+        // Like the average depth, the averages include the Root, whose height is 0, so
+        // that they can be compared to the average depth. The distributions do not.
+        let sum_of_heights = f64::value_from(self.sum_of_heights)?;
+        let sum_of_gaps = f64::value_from(self.sum_of_gaps)?;
+
+        Ok(Box::new(HeightsResult {
+            average_height: sum_of_heights / (n_transactions + 1.0),
+            heights: self.heights.clone(),
+            average_gap: sum_of_gaps / (n_transactions + 1.0),
+            gaps: self.gaps.clone(),
+        }))
+    }
+}
+
+/// The result of the throughput statistic.
+pub struct ThroughputResult {
+    unit: TimeUnit,
+    txs_per_unit: f64,
+    average_inter_arrival: f64,
+}
+
+impl Display for ThroughputResult {
+    fn fmt(&self, f: &mut fmt::Formatter) -> fmt::Result {
+        let rate = Rate {
+            per_unit: self.txs_per_unit,
+            unit: self.unit,
+        };
+        let duration = Duration {
+            length: self.average_inter_arrival,
+            unit: self.unit,
+        };
+        writeln!(f, "> THROUGHPUT: {}", rate)?;
+        write!(f, "> AVG INTER-ARRIVAL TIME: {}", duration)
+    }
+}
+
+impl StatResult for ThroughputResult {
+    fn values(&self) -> Vec<(&'static str, f64)> {
+        vec![
+            ("THROUGHPUT", self.txs_per_unit),
+            ("AVG INTER-ARRIVAL TIME", self.average_inter_arrival),
+        ]
     }
 }
 
-/// The accumulator for the largest timestamp.
+/// The accumulator for the time span of the transactions. The throughput is the number of
+/// intervals between transactions over the span between the smallest and the largest
+/// timestamps, and the average inter-arrival time is its reciprocal.
 #[derive(Default)]
-pub struct TimeUnits {
-    max_timestamp: usize,
+pub struct Throughput {
+    /// The unit of the timestamps. When it is not known beforehand, it is taken from the
+    /// metadata of the graph the first time a transaction is observed.
+    pub(crate) unit: Option<TimeUnit>,
+
+    /// The smallest and largest timestamps.
+    span: Option<(Timestamp, Timestamp)>,
 }
 
-impl Stat<'_> for TimeUnits {
+impl Throughput {
+    /// The throughput in the time unit of the graph.
+    pub fn new(graph: &Graph) -> Self {
+        Self::with_unit(TimeUnit::of(graph))
+    }
+
+    pub fn with_unit(unit: TimeUnit) -> Self {
+        Self {
+            unit: Some(unit),
+            span: None,
+        }
+    }
+}
+
+impl Stat<'_> for Throughput {
     fn accumulate(&mut self, transaction: &Transaction) {
-        self.max_timestamp = self.max_timestamp.max(transaction.timestamp())
+        let timestamp = transaction.timestamp();
+        self.span = Some(self.span.map_or((timestamp, timestamp), |(min, max)| {
+            (min.min(timestamp), max.max(timestamp))
+        }));
     }
 
-    fn result(&self, n_transactions: f64) -> Result<Box<dyn Display>, PosOverflow<usize>> {
-        let max_timestamp = f64::value_from(self.max_timestamp)?;
-        Ok(Box::new(TimeUnitsResult {
-            average_txs_per_time_unit: max_timestamp / n_transactions,
+    fn result(&self, n_transactions: f64) -> Result<Box<dyn StatResult>, PosOverflow<usize>> {
+        let (min, max) = self.span.unwrap_or((0, 0));
+        let span = span_to_f64(max - min)?;
+        // Without intervals between transactions, both are 0 / 0.
+        let n_intervals = (n_transactions - 1.0).max(0.0);
+        Ok(Box::new(ThroughputResult {
+            unit: self.unit.unwrap_or_default(),
+            txs_per_unit: n_intervals / span,
+            average_inter_arrival: span / n_intervals,
         }))
     }
 }
 
+/// Convert a span of time to a float, reporting spans that do not fit like the counts
+/// that do not fit.
+pub(crate) fn span_to_f64(span: Timestamp) -> Result<f64, PosOverflow<usize>> {
+    f64::value_from(span).map_err(|e| PosOverflow(usize::try_from(e.0).unwrap_or(usize::MAX)))
+}
+
 /// The result of the statistic related to timestamps.
 pub struct TimestampsResult {
     average_txs_per_timestamp: f64,
@@ -189,9 +412,16 @@ impl Display for TimestampsResult {
     }
 }
 
+impl StatResult for TimestampsResult {
+    fn values(&self) -> Vec<(&'static str, f64)> {
+        vec![("AVG TXS PER TIMESTAMP", self.average_txs_per_timestamp)]
+    }
+}
+
 /// The accumulator for timestamps.
+#[derive(Default)]
 pub struct Timestamps {
-    unique_timestamps: Set<usize>,
+    unique_timestamps: Set<Timestamp>,
 }
 
 impl Timestamps {
@@ -207,10 +437,92 @@ impl Stat<'_> for Timestamps {
         self.unique_timestamps.insert(transaction.timestamp());
     }
 
-    fn result(&self, n_transactions: f64) -> Result<Box<dyn Display>, PosOverflow<usize>> {
+    fn result(&self, n_transactions: f64) -> Result<Box<dyn StatResult>, PosOverflow<usize>> {
         let n_unique_timestamps = f64::value_from(self.unique_timestamps.len())?;
         Ok(Box::new(TimestampsResult {
             average_txs_per_timestamp: n_transactions / n_unique_timestamps,
         }))
     }
 }
+
+/// A distribution of values, as the number of times each value occurs.
+#[derive(PartialEq, Debug, Default, Clone)]
+pub struct Distribution<T = u64> {
+    counts: BTreeMap<T, usize>,
+    len: usize,
+}
+
+impl<T: Copy + Ord + Into<i128>> Distribution<T> {
+    pub(crate) fn add(&mut self, value: T, count: usize) {
+        if count > 0 {
+            *self.counts.entry(value).or_insert(0) += count;
+            self.len += count;
+        }
+    }
+
+    /// The number of values.
+    pub fn len(&self) -> usize {
+        self.len
+    }
+
+    pub fn is_empty(&self) -> bool {
+        self.len == 0
+    }
+
+    /// The distinct values in increasing order, along with the number of times they occur.
+    pub fn iter(&self) -> impl Iterator<Item = (T, usize)> + '_ {
+        self.counts.iter().map(|(&value, &count)| (value, count))
+    }
+
+    /// The smallest value such that at least a fraction `q` of the values are lower or
+    /// equal to it, or None if there are no values.
+    pub fn quantile(&self, q: f64) -> Option<T> {
+        let rank = (q * self.len as f64).ceil().max(1.0);
+        let mut seen = 0;
+
+        for (value, count) in self.iter() {
+            seen += count;
+            if seen as f64 >= rank {
+                return Some(value);
+            }
+        }
+
+        self.counts.keys().next_back().copied()
+    }
+
+    /// The minimum, the median, the 90th percentile and the maximum of the values.
+    pub fn summary(&self) -> [Option<T>; 4] {
+        [0.0, 0.5, 0.9, 1.0].map(|q| self.quantile(q))
+    }
+
+    /// The mean and the standard deviation of the values, NaN if there are none.
+    pub fn mean_and_deviation(&self) -> Result<(f64, f64), PosOverflow<usize>> {
+        let (mut sum, mut sum_of_squares) = (0.0, 0.0);
+        for (value, count) in self.iter() {
+            // Every value is within the range of f64, only its precision may be lost.
+            let value = value.into() as f64;
+            let count = f64::value_from(count)?;
+            sum += value * count;
+            sum_of_squares += value * value * count;
+        }
+
+        let len = f64::value_from(self.len)?;
+        let mean = sum / len;
+        // Rounding can make the variance of equal values slightly negative.
+        let variance = (sum_of_squares / len - mean * mean).max(0.0);
+        Ok((mean, variance.sqrt()))
+    }
+}
+
+impl<T: Copy + Ord + Into<i128> + Display> Display for Distribution<T> {
+    fn fmt(&self, f: &mut fmt::Formatter) -> fmt::Result {
+        let [min, median, p90, max] = self
+            .summary()
+            .map(|value| value.map_or_else(|| "NaN".to_string(), |value| value.to_string()));
+        write!(
+            f,
+            "MIN {}, MEDIAN {}, P90 {}, MAX {}",
+            min, median, p90, max
+        )
+    }
+}
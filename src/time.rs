@@ -0,0 +1,391 @@
+#![warn(clippy::all)]
+
+//! Timestamps of transactions, their units and how durations and rates are displayed.
+
+use crate::graph::Graph;
+use crate::metadata::Metadata;
+use derive_more::Display;
+use std::convert::TryFrom;
+use std::error;
+use std::fmt::{self, Display};
+use std::num::ParseIntError;
+use std::ops::Range;
+use std::str::FromStr;
+
+/// Errors when dealing with timestamps and units of time.
+#[derive(PartialEq, Eq, Debug, Display)]
+pub enum Error {
+    #[display(fmt = "Unknown time unit `{}`, expected ticks, ms or s", _0)]
+    UnknownUnit(String),
+
+    #[display(fmt = "Invalid number")]
+    InvalidNumber(ParseIntError),
+
+    #[display(fmt = "Invalid RFC 3339 date and time")]
+    InvalidDateTime,
+
+    #[display(fmt = "Date and time before the Unix epoch")]
+    BeforeEpoch,
+
+    #[display(fmt = "Dates and times are read in ms, but the time unit is `{}`", _0)]
+    DateTimeUnit(String),
+}
+
+impl error::Error for Error {
+    fn source(&self) -> Option<&(dyn error::Error + 'static)> {
+        match self {
+            Error::InvalidNumber(e) => Some(e),
+            Error::UnknownUnit(_)
+            | Error::InvalidDateTime
+            | Error::BeforeEpoch
+            | Error::DateTimeUnit(_) => None,
+        }
+    }
+}
+
+/// A point in time, as a number of units of the time unit of its graph. Wall-clock
+/// timestamps count from the Unix epoch.
+pub type Timestamp = u64;
+
+/// Parse a timestamp, either as a number of units or as an RFC 3339 date and time such as
+/// `2024-05-01T12:00:00.250Z`. Dates and times are read as milliseconds since the Unix
+/// epoch, see `declare_date_times`.
+pub fn parse_timestamp(input: &str) -> Result<Timestamp, Error> {
+    if is_date_time(input) {
+        parse_rfc3339(input)
+    } else {
+        Timestamp::from_str(input).map_err(Error::InvalidNumber)
+    }
+}
+
+/// Whether a timestamp is written as an RFC 3339 date and time rather than as a number.
+pub fn is_date_time(input: &str) -> bool {
+    input.len() > 4 && input.as_bytes()[4] == b'-'
+}
+
+/// Record in the metadata of a graph that some of its timestamps were given as dates and
+/// times. Since those are read as milliseconds, `ms` becomes the time unit when none is
+/// declared, and any other declared unit is an error.
+pub fn declare_date_times(metadata: &mut Metadata) -> Result<(), Error> {
+    match metadata.time_unit() {
+        None => {
+            metadata.insert(Metadata::TIME_UNIT, "ms");
+            Ok(())
+        }
+        Some("ms") => Ok(()),
+        Some(unit) => Err(Error::DateTimeUnit(String::from(unit))),
+    }
+}
+
+/// Parse an RFC 3339 date and time into milliseconds since the Unix epoch. Fractions of
+/// milliseconds are truncated.
+fn parse_rfc3339(input: &str) -> Result<Timestamp, Error> {
+    let bytes = input.as_bytes();
+    let number = |range: Range<usize>| -> Result<i64, Error> {
+        let digits = bytes.get(range).ok_or(Error::InvalidDateTime)?;
+        digits.iter().try_fold(0, |value, &b| match b {
+            b'0'..=b'9' => Ok(value * 10 + i64::from(b - b'0')),
+            _ => Err(Error::InvalidDateTime),
+        })
+    };
+    let separator = |i: usize, expected: &[u8]| match bytes.get(i) {
+        Some(b) if expected.contains(b) => Ok(()),
+        _ => Err(Error::InvalidDateTime),
+    };
+
+    // YYYY-MM-DDTHH:MM:SS
+    let year = number(0..4)?;
+    separator(4, b"-")?;
+    let month = number(5..7)?;
+    separator(7, b"-")?;
+    let day = number(8..10)?;
+    separator(10, b"Tt ")?;
+    let hour = number(11..13)?;
+    separator(13, b":")?;
+    let minute = number(14..16)?;
+    separator(16, b":")?;
+    let second = number(17..19)?;
+
+    let days_in_month = match month {
+        1 | 3 | 5 | 7 | 8 | 10 | 12 => 31,
+        4 | 6 | 9 | 11 => 30,
+        2 if year % 4 == 0 && (year % 100 != 0 || year % 400 == 0) => 29,
+        2 => 28,
+        _ => return Err(Error::InvalidDateTime),
+    };
+    // Leap seconds are accepted and folded into the next second.
+    if day < 1 || day > days_in_month || hour > 23 || minute > 59 || second > 60 {
+        return Err(Error::InvalidDateTime);
+    }
+
+    // Optional fraction of a second, of which milliseconds are kept.
+    let mut i = 19;
+    let mut millis = 0;
+    if bytes.get(i) == Some(&b'.') {
+        i += 1;
+        let start = i;
+        while bytes.get(i).is_some_and(u8::is_ascii_digit) {
+            if i - start < 3 {
+                millis = millis * 10 + i64::from(bytes[i] - b'0');
+            }
+            i += 1;
+        }
+        if i == start {
+            return Err(Error::InvalidDateTime);
+        }
+        for _ in i - start..3 {
+            millis *= 10;
+        }
+    }
+
+    // Offset from UTC.
+    let offset = match bytes.get(i) {
+        Some(b'Z') | Some(b'z') if i + 1 == bytes.len() => 0,
+        Some(&sign) if (sign == b'+' || sign == b'-') && i + 6 == bytes.len() => {
+            separator(i + 3, b":")?;
+            let offset = number(i + 1..i + 3)? * 60 + number(i + 4..i + 6)?;
+            if sign == b'+' {
+                offset
+            } else {
+                -offset
+            }
+        }
+        _ => return Err(Error::InvalidDateTime),
+    };
+
+    let days = days_from_civil(year, month, day);
+    let seconds = ((days * 24 + hour) * 60 + minute - offset) * 60 + second;
+    let millis = seconds * 1000 + millis;
+
+    Timestamp::try_from(millis).map_err(|_| Error::BeforeEpoch)
+}
+
+/// The number of days between the Unix epoch and a date of the proleptic Gregorian
+/// calendar, negative before the epoch.
+fn days_from_civil(year: i64, month: i64, day: i64) -> i64 {
+    // Count years from March so that leap days end the year.
+    let year = if month <= 2 { year - 1 } else { year };
+    let era = year.div_euclid(400);
+    let year_of_era = year - era * 400;
+    let day_of_year = (153 * ((month + 9) % 12) + 2) / 5 + day - 1;
+    let day_of_era = year_of_era * 365 + year_of_era / 4 - year_of_era / 100 + day_of_year;
+    era * 146_097 + day_of_era - 719_468
+}
+
+/// The unit of the timestamps of a graph, displayed as a plural.
+#[derive(Clone, Copy, PartialEq, Eq, Debug, Default, Display)]
+pub enum TimeUnit {
+    /// Logical time, such as rounds of a simulation.
+    #[default]
+    #[display(fmt = "ticks")]
+    Ticks,
+
+    #[display(fmt = "ms")]
+    Milliseconds,
+
+    #[display(fmt = "s")]
+    Seconds,
+}
+
+impl FromStr for TimeUnit {
+    type Err = Error;
+
+    fn from_str(s: &str) -> Result<Self, Self::Err> {
+        match s {
+            "ticks" | "tick" => Ok(TimeUnit::Ticks),
+            "ms" => Ok(TimeUnit::Milliseconds),
+            "s" => Ok(TimeUnit::Seconds),
+            _ => Err(Error::UnknownUnit(String::from(s))),
+        }
+    }
+}
+
+impl TimeUnit {
+    /// The unit declared in the metadata of a graph, ticks if none is declared.
+    pub fn from_metadata(metadata: &Metadata) -> Result<Self, Error> {
+        metadata.time_unit().map_or(Ok(TimeUnit::Ticks), str::parse)
+    }
+
+    /// The unit declared in the metadata of a graph, ticks when the graph declares no
+    /// unit or an unknown one.
+    pub fn of(graph: &Graph) -> Self {
+        Self::from_metadata(graph.metadata()).unwrap_or_default()
+    }
+
+    /// The length of the unit in seconds, None for logical time.
+    pub fn seconds(self) -> Option<f64> {
+        match self {
+            TimeUnit::Ticks => None,
+            TimeUnit::Milliseconds => Some(0.001),
+            TimeUnit::Seconds => Some(1.0),
+        }
+    }
+}
+
+/// The units that wall-clock durations and rates are displayed in, with their length in
+/// seconds, from the longest to the shortest.
+const HUMAN_UNITS: [(&str, f64); 5] = [
+    ("d", 86_400.0),
+    ("h", 3_600.0),
+    ("min", 60.0),
+    ("s", 1.0),
+    ("ms", 0.001),
+];
+
+/// A duration in the time unit of a graph. Wall-clock durations are displayed in the
+/// longest unit they span at least one of, such as `1.30 s` or `250.00 ms`.
+#[derive(Clone, Copy, PartialEq, Debug)]
+pub struct Duration {
+    pub length: f64,
+    pub unit: TimeUnit,
+}
+
+impl Display for Duration {
+    fn fmt(&self, f: &mut fmt::Formatter) -> fmt::Result {
+        let seconds = match self.unit.seconds() {
+            Some(seconds) => self.length * seconds,
+            None => return write!(f, "{:.2} {}", self.length, self.unit),
+        };
+
+        let (name, length) = if seconds.is_finite() && seconds != 0.0 {
+            HUMAN_UNITS
+                .iter()
+                .find(|(_, length)| seconds.abs() >= *length)
+                .unwrap_or(&HUMAN_UNITS[4])
+        } else {
+            &HUMAN_UNITS[3]
+        };
+        write!(f, "{:.2} {}", seconds / length, name)
+    }
+}
+
+/// A number of transactions per time unit of a graph. Wall-clock rates are displayed per
+/// the shortest unit in which at least one transaction happens, such as `4.20 TXS/s` or
+/// `3.00 TXS/h`.
+#[derive(Clone, Copy, PartialEq, Debug)]
+pub struct Rate {
+    pub per_unit: f64,
+    pub unit: TimeUnit,
+}
+
+impl Display for Rate {
+    fn fmt(&self, f: &mut fmt::Formatter) -> fmt::Result {
+        let per_second = match self.unit.seconds() {
+            Some(seconds) => self.per_unit / seconds,
+            None => return write!(f, "{:.2} TXS/tick", self.per_unit),
+        };
+
+        let (name, length) = if per_second.is_finite() && per_second != 0.0 {
+            HUMAN_UNITS[..4]
+                .iter()
+                .rev()
+                .find(|(_, length)| per_second * length >= 1.0)
+                .unwrap_or(&HUMAN_UNITS[0])
+        } else {
+            &HUMAN_UNITS[3]
+        };
+        write!(f, "{:.2} TXS/{}", per_second * length, name)
+    }
+}
+
+#[cfg(test)]
+mod time_tests {
+    use super::{declare_date_times, parse_timestamp, Duration, Error, Rate, TimeUnit};
+    use crate::metadata::Metadata;
+
+    #[test]
+    fn parse_unit() {
+        assert_eq!("ticks".parse(), Ok(TimeUnit::Ticks));
+        assert_eq!("ms".parse(), Ok(TimeUnit::Milliseconds));
+        assert_eq!("s".parse(), Ok(TimeUnit::Seconds));
+        assert_eq!(
+            "hours".parse::<TimeUnit>(),
+            Err(Error::UnknownUnit(String::from("hours")))
+        );
+
+        let mut metadata = Metadata::default();
+        assert_eq!(TimeUnit::from_metadata(&metadata), Ok(TimeUnit::Ticks));
+        metadata.insert(Metadata::TIME_UNIT, "ms");
+        assert_eq!(
+            TimeUnit::from_metadata(&metadata),
+            Ok(TimeUnit::Milliseconds)
+        );
+    }
+
+    #[test]
+    fn parse_timestamps() {
+        assert_eq!(parse_timestamp("120"), Ok(120));
+        assert_eq!(parse_timestamp("1714564800250"), Ok(1_714_564_800_250));
+        assert_eq!(parse_timestamp("1970-01-01T00:00:00Z"), Ok(0));
+        assert_eq!(
+            parse_timestamp("2024-05-01T12:00:00.250Z"),
+            Ok(1_714_564_800_250)
+        );
+        assert_eq!(
+            parse_timestamp("2024-05-01t14:00:00.2509+02:00"),
+            Ok(1_714_564_800_250)
+        );
+        assert_eq!(
+            parse_timestamp("2024-02-29 00:00:00-01:30"),
+            Ok(1_709_170_200_000)
+        );
+
+        assert!(matches!(
+            parse_timestamp("12a"),
+            Err(Error::InvalidNumber(_))
+        ));
+        assert!(matches!(
+            parse_timestamp("-12"),
+            Err(Error::InvalidNumber(_))
+        ));
+        assert_eq!(
+            parse_timestamp("2023-02-29T00:00:00Z"),
+            Err(Error::InvalidDateTime)
+        );
+        assert_eq!(
+            parse_timestamp("2024-05-01T12:00:00"),
+            Err(Error::InvalidDateTime)
+        );
+        assert_eq!(
+            parse_timestamp("2024-05-01T12:00:00.Z"),
+            Err(Error::InvalidDateTime)
+        );
+        assert_eq!(
+            parse_timestamp("1969-12-31T23:59:59Z"),
+            Err(Error::BeforeEpoch)
+        );
+    }
+
+    #[test]
+    fn date_time_unit() {
+        let mut metadata = Metadata::default();
+        assert_eq!(declare_date_times(&mut metadata), Ok(()));
+        assert_eq!(metadata.time_unit(), Some("ms"));
+        assert_eq!(declare_date_times(&mut metadata), Ok(()));
+
+        metadata.insert(Metadata::TIME_UNIT, "s");
+        assert_eq!(
+            declare_date_times(&mut metadata),
+            Err(Error::DateTimeUnit(String::from("s")))
+        );
+        assert_eq!(metadata.time_unit(), Some("s"));
+    }
+
+    #[test]
+    fn human_units() {
+        let duration = |length, unit| Duration { length, unit }.to_string();
+        assert_eq!(duration(0.75, TimeUnit::Ticks), "0.75 ticks");
+        assert_eq!(duration(1300.0, TimeUnit::Milliseconds), "1.30 s");
+        assert_eq!(duration(250.0, TimeUnit::Milliseconds), "250.00 ms");
+        assert_eq!(duration(5400.0, TimeUnit::Seconds), "1.50 h");
+        assert_eq!(duration(0.5, TimeUnit::Milliseconds), "0.50 ms");
+        assert_eq!(duration(0.0, TimeUnit::Seconds), "0.00 s");
+        assert_eq!(duration(f64::NAN, TimeUnit::Seconds), "NaN s");
+
+        let rate = |per_unit, unit| Rate { per_unit, unit }.to_string();
+        assert_eq!(rate(1.5, TimeUnit::Ticks), "1.50 TXS/tick");
+        assert_eq!(rate(0.0042, TimeUnit::Milliseconds), "4.20 TXS/s");
+        assert_eq!(rate(0.05, TimeUnit::Seconds), "3.00 TXS/min");
+        assert_eq!(rate(f64::INFINITY, TimeUnit::Seconds), "inf TXS/s");
+    }
+}
@@ -3,14 +3,19 @@
 //! Transaction data structures.
 
 use crate::id::{self, Id, NonRootId};
+use crate::time::{self, Timestamp};
 use derive_more::Display;
 use std::convert::TryFrom;
+use std::error;
 use std::num::ParseIntError;
 use std::str::FromStr;
 
 /// Errors that can happen when dealing with transactions.
 #[derive(PartialEq, Eq, Debug, Display)]
 pub enum Error {
+    #[display(fmt = "Missing identifier")]
+    MissingId,
+
     #[display(fmt = "Missing left reference")]
     MissingLeft,
 
@@ -20,25 +25,60 @@ pub enum Error {
     #[display(fmt = "Missing timestamp")]
     MissingTimestamp,
 
-    #[display(fmt = "Invalid Id: {}", "_0")]
+    #[display(fmt = "Invalid Id")]
     InvalidId(id::Error),
 
-    #[display(fmt = "Invalid left reference: {}", "_0")]
+    #[display(fmt = "Invalid left reference")]
     InvalidLeft(ParseIntError),
 
-    #[display(fmt = "Invalid right reference: {}", "_0")]
+    #[display(fmt = "Invalid right reference")]
     InvalidRight(ParseIntError),
 
-    #[display(fmt = "Invalid timestamp: {}", "_0")]
-    InvalidTimestamp(ParseIntError),
+    #[display(fmt = "Invalid timestamp")]
+    InvalidTimestamp(time::Error),
 
-    #[display(fmt = "Invalid left id: {}", "_0")]
+    #[display(fmt = "Invalid left id")]
     InvalidLeftId(id::Error),
 
-    #[display(fmt = "Invalid right id: {}", "_0")]
+    #[display(fmt = "Invalid right id")]
     InvalidRightId(id::Error),
 }
 
+impl Error {
+    /// The field of the transaction the error is about.
+    pub fn field(&self) -> Field {
+        match self {
+            Error::MissingId | Error::InvalidId(_) => Field::Id,
+            Error::MissingLeft | Error::InvalidLeft(_) | Error::InvalidLeftId(_) => Field::Left,
+            Error::MissingRight | Error::InvalidRight(_) | Error::InvalidRightId(_) => Field::Right,
+            Error::MissingTimestamp | Error::InvalidTimestamp(_) => Field::Timestamp,
+        }
+    }
+}
+
+impl error::Error for Error {
+    fn source(&self) -> Option<&(dyn error::Error + 'static)> {
+        match self {
+            Error::InvalidId(e) | Error::InvalidLeftId(e) | Error::InvalidRightId(e) => Some(e),
+            Error::InvalidLeft(e) | Error::InvalidRight(e) => Some(e),
+            Error::InvalidTimestamp(e) => Some(e),
+            Error::MissingId
+            | Error::MissingLeft
+            | Error::MissingRight
+            | Error::MissingTimestamp => None,
+        }
+    }
+}
+
+/// The fields of a transaction as found in inputs.
+#[derive(Clone, Copy, PartialEq, Eq, Debug)]
+pub enum Field {
+    Id,
+    Left,
+    Right,
+    Timestamp,
+}
+
 /// The `Transaction` structure with left and right references and a timestamp.
 #[derive(PartialEq, Eq, Debug, Display)]
 #[display(fmt = "Tx<{}, {}, {}, {}>", id, left, right, timestamp)]
@@ -46,11 +86,11 @@ pub struct Transaction {
     id: NonRootId,
     left: Id,
     right: Id,
-    timestamp: usize,
+    timestamp: Timestamp,
 }
 
 impl Transaction {
-    pub fn new(id: NonRootId, left: Id, right: Id, timestamp: usize) -> Self {
+    pub fn new(id: NonRootId, left: Id, right: Id, timestamp: Timestamp) -> Self {
         Self {
             id,
             left,
@@ -71,16 +111,17 @@ impl Transaction {
         self.right
     }
 
-    pub fn timestamp(&self) -> usize {
+    pub fn timestamp(&self) -> Timestamp {
         self.timestamp
     }
 }
 
-impl TryFrom<(usize, &String)> for Transaction {
+impl<S: AsRef<str>> TryFrom<(usize, S)> for Transaction {
     type Error = Error;
 
-    fn try_from((id, input): (usize, &String)) -> Result<Self, Self::Error> {
+    fn try_from((id, input): (usize, S)) -> Result<Self, Self::Error> {
         let id = NonRootId::try_from(id).map_err(Error::InvalidId)?;
+        let input = input.as_ref();
 
         let mut iter = input.split_ascii_whitespace();
 
@@ -96,16 +137,112 @@ impl TryFrom<(usize, &String)> for Transaction {
 
         // Read the timestamp.
         let timestamp = iter.next().ok_or(Error::MissingTimestamp)?;
-        let timestamp = usize::from_str(timestamp).map_err(Error::InvalidTimestamp)?;
+        let timestamp = time::parse_timestamp(timestamp).map_err(Error::InvalidTimestamp)?;
+
+        Ok(Self::new(id, left, right, timestamp))
+    }
+}
+
+impl Transaction {
+    /// Parse a `LID RID TIMESTAMP` line like `Transaction::try_from`, but directly from
+    /// bytes and without allocating.
+    pub fn parse_bytes(id: usize, input: &[u8]) -> Result<Self, Error> {
+        let id = NonRootId::try_from(id).map_err(Error::InvalidId)?;
+
+        let mut iter = input
+            .split(u8::is_ascii_whitespace)
+            .filter(|token| !token.is_empty());
+
+        // Read the left reference.
+        let left = iter.next().ok_or(Error::MissingLeft)?;
+        let left = parse_usize(left).map_err(Error::InvalidLeft)?;
+        let left = Id::try_from(left).map_err(Error::InvalidLeftId)?;
+
+        // Read the right reference.
+        let right = iter.next().ok_or(Error::MissingRight)?;
+        let right = parse_usize(right).map_err(Error::InvalidRight)?;
+        let right = Id::try_from(right).map_err(Error::InvalidRightId)?;
+
+        // Read the timestamp.
+        let timestamp = iter.next().ok_or(Error::MissingTimestamp)?;
+        let timestamp = parse_timestamp(timestamp).map_err(Error::InvalidTimestamp)?;
 
         Ok(Self::new(id, left, right, timestamp))
     }
 }
 
+/// Parse plain decimal digits directly from bytes, returning None when anything else is
+/// found or when the number does not fit in a `u64`.
+fn parse_digits(input: &[u8]) -> Option<u64> {
+    if input.is_empty() {
+        return None;
+    }
+
+    input.iter().try_fold(0u64, |value, &b| {
+        let digit = b.wrapping_sub(b'0');
+        if digit < 10 {
+            value.checked_mul(10)?.checked_add(u64::from(digit))
+        } else {
+            None
+        }
+    })
+}
+
+/// Parse a decimal number directly from bytes. Anything but plain digits that fit in a
+/// `usize` is left to `usize::from_str` so that the same errors are reported.
+fn parse_usize(input: &[u8]) -> Result<usize, ParseIntError> {
+    match parse_digits(input).map(usize::try_from) {
+        Some(Ok(value)) => Ok(value),
+        _ => usize::from_str(&String::from_utf8_lossy(input)),
+    }
+}
+
+/// Parse a timestamp directly from bytes. Anything but plain digits, such as dates and
+/// times, is left to `time::parse_timestamp`.
+fn parse_timestamp(input: &[u8]) -> Result<Timestamp, time::Error> {
+    match parse_digits(input) {
+        Some(value) => Ok(value),
+        None => time::parse_timestamp(&String::from_utf8_lossy(input)),
+    }
+}
+
+/// A transaction as found in inputs with textual identifiers, before the identifiers are
+/// interned into `Id`s.
+#[derive(PartialEq, Eq, Debug)]
+pub struct NamedTransaction<'a> {
+    pub id: &'a str,
+    pub left: &'a str,
+    pub right: &'a str,
+    pub timestamp: Timestamp,
+}
+
+impl<'a> TryFrom<&'a str> for NamedTransaction<'a> {
+    type Error = Error;
+
+    fn try_from(input: &'a str) -> Result<Self, Self::Error> {
+        let mut iter = input.split_ascii_whitespace();
+
+        let id = iter.next().ok_or(Error::MissingId)?;
+        let left = iter.next().ok_or(Error::MissingLeft)?;
+        let right = iter.next().ok_or(Error::MissingRight)?;
+
+        let timestamp = iter.next().ok_or(Error::MissingTimestamp)?;
+        let timestamp = time::parse_timestamp(timestamp).map_err(Error::InvalidTimestamp)?;
+
+        Ok(Self {
+            id,
+            left,
+            right,
+            timestamp,
+        })
+    }
+}
+
 #[cfg(test)]
 mod transaction_tests {
     use super::{Error, Transaction};
     use crate::id::{self, Id, NonRootId};
+    use crate::time;
     use std::convert::TryFrom;
 
     #[test]
@@ -167,7 +304,8 @@ mod transaction_tests {
     fn parse_invalid_timestamp() {
         let input = String::from("5 6 abc");
         let res = Transaction::try_from((2, &input));
-        let err = "abc".parse::<usize>().err().unwrap();
+        let err = "abc".parse::<u64>().err().unwrap();
+        let err = time::Error::InvalidNumber(err);
         assert_eq!(res, Err(Error::InvalidTimestamp(err)));
     }
 
@@ -185,3 +323,91 @@ mod transaction_tests {
         assert_eq!(res, Err(Error::InvalidRightId(id::Error::Invalid)));
     }
 }
+
+#[cfg(test)]
+mod parse_bytes_tests {
+    use super::{parse_usize, Transaction};
+    use std::convert::TryFrom;
+
+    #[test]
+    fn same_as_try_from() {
+        let inputs = [
+            "5 6 120",
+            "1 1 120",
+            "",
+            "5",
+            "5 6",
+            "abc",
+            "5 abc",
+            "5 6 abc",
+            "0 5 120",
+            "5 0 120",
+            " 5\t6  +120 ",
+            "5 6 99999999999999999999999",
+            "5 6 2024-05-01T12:00:00.250Z",
+            "5 6 2024-13-01T12:00:00Z",
+        ];
+
+        for input in inputs.iter() {
+            assert_eq!(
+                Transaction::parse_bytes(2, input.as_bytes()),
+                Transaction::try_from((2, input)),
+            );
+        }
+
+        assert_eq!(
+            Transaction::parse_bytes(1, b"5 6 120"),
+            Transaction::try_from((1, "5 6 120")),
+        );
+    }
+
+    #[test]
+    fn parse_numbers() {
+        assert_eq!(parse_usize(b"0"), Ok(0));
+        assert_eq!(parse_usize(b"18446744073709551615"), Ok(usize::MAX));
+        assert_eq!(parse_usize(b"-1"), "-1".parse::<usize>());
+        assert_eq!(parse_usize(b""), "".parse::<usize>());
+        assert_eq!(parse_usize(b"1\xff"), "1x".parse::<usize>());
+    }
+}
+
+#[cfg(test)]
+mod named_transaction_tests {
+    use super::{Error, NamedTransaction};
+    use crate::time;
+    use std::convert::TryFrom;
+
+    #[test]
+    fn parse_success() {
+        let res = NamedTransaction::try_from("c7 a3 9b 120");
+        let expected = NamedTransaction {
+            id: "c7",
+            left: "a3",
+            right: "9b",
+            timestamp: 120,
+        };
+        assert_eq!(res, Ok(expected));
+    }
+
+    #[test]
+    fn parse_missing() {
+        assert_eq!(NamedTransaction::try_from(""), Err(Error::MissingId));
+        assert_eq!(NamedTransaction::try_from("c7"), Err(Error::MissingLeft));
+        assert_eq!(
+            NamedTransaction::try_from("c7 a3"),
+            Err(Error::MissingRight)
+        );
+        assert_eq!(
+            NamedTransaction::try_from("c7 a3 9b"),
+            Err(Error::MissingTimestamp)
+        );
+    }
+
+    #[test]
+    fn parse_invalid_timestamp() {
+        let res = NamedTransaction::try_from("c7 a3 9b abc");
+        let err = "abc".parse::<u64>().err().unwrap();
+        let err = time::Error::InvalidNumber(err);
+        assert_eq!(res, Err(Error::InvalidTimestamp(err)));
+    }
+}
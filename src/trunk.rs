@@ -0,0 +1,283 @@
+#![warn(clippy::all)]
+
+//! Statistics that tell the two references apart: the left reference of a transaction is
+//! its trunk and the right reference is its branch.
+
+use crate::graph::Graph;
+use crate::id::{Id, NonRootId};
+use crate::stats::{span_to_f64, Stat, StatResult};
+use crate::time::{Duration, TimeUnit, Timestamp};
+use crate::transaction::Transaction;
+use conv::{errors::PosOverflow, ValueFrom};
+use std::collections::HashMap as Map;
+use std::fmt::{self, Display};
+
+impl Graph {
+    /// The number of transactions on the path from a transaction to the Root that only
+    /// follows trunks, including the transaction itself.
+    pub fn trunk_length(&self, id: NonRootId, cache: &mut Map<NonRootId, usize>) -> usize {
+        // Walk down the trunks until the Root or a known length, then fill the cache on
+        // the way back so that long chains do not recurse.
+        let mut chain = Vec::new();
+        let mut next = Id::Transaction(id);
+        let mut length = 0;
+
+        while let Id::Transaction(id) = next {
+            if let Some(&known) = cache.get(&id) {
+                length = known;
+                break;
+            }
+            chain.push(id);
+            next = self[id].left();
+        }
+
+        for id in chain.into_iter().rev() {
+            length += 1;
+            cache.insert(id, length);
+        }
+
+        length
+    }
+}
+
+/// The sum and the number of the ages of the transactions referred to by one side.
+#[derive(Default)]
+struct Ages {
+    sum: f64,
+    count: usize,
+}
+
+impl Ages {
+    /// Add the time between a transaction and the transaction it refers to, negative when
+    /// the referred transaction is more recent.
+    fn add(&mut self, timestamp: Timestamp, parent: Timestamp) -> Result<(), PosOverflow<usize>> {
+        self.sum += if timestamp >= parent {
+            span_to_f64(timestamp - parent)?
+        } else {
+            -span_to_f64(parent - timestamp)?
+        };
+        self.count += 1;
+        Ok(())
+    }
+
+    fn average(&self) -> Result<f64, PosOverflow<usize>> {
+        Ok(self.sum / f64::value_from(self.count)?)
+    }
+}
+
+/// The result of trunk and branch statistics.
+pub struct TrunkBranchResult {
+    pub(crate) unit: TimeUnit,
+    pub(crate) average_trunk_length: f64,
+    pub(crate) max_trunk_length: f64,
+    pub(crate) average_trunk_age: f64,
+    pub(crate) average_branch_age: f64,
+    pub(crate) same_trunk_and_branch: f64,
+    pub(crate) trunk_root_approvals: f64,
+    pub(crate) branch_root_approvals: f64,
+}
+
+impl Display for TrunkBranchResult {
+    fn fmt(&self, f: &mut fmt::Formatter) -> fmt::Result {
+        let duration = |length| Duration {
+            length,
+            unit: self.unit,
+        };
+        writeln!(
+            f,
+            "> TRUNK CHAIN: AVG {:.2}, MAX {}",
+            self.average_trunk_length, self.max_trunk_length
+        )?;
+        writeln!(
+            f,
+            "> AVG PARENT AGE: TRUNK {}, BRANCH {}",
+            duration(self.average_trunk_age),
+            duration(self.average_branch_age)
+        )?;
+        writeln!(f, "> SAME TRUNK AND BRANCH: {}", self.same_trunk_and_branch)?;
+        write!(
+            f,
+            "> ROOT APPROVALS: TRUNK {}, BRANCH {}",
+            self.trunk_root_approvals, self.branch_root_approvals
+        )
+    }
+}
+
+impl StatResult for TrunkBranchResult {
+    fn values(&self) -> Vec<(&'static str, f64)> {
+        vec![
+            ("AVG TRUNK CHAIN", self.average_trunk_length),
+            ("MAX TRUNK CHAIN", self.max_trunk_length),
+            ("AVG TRUNK AGE", self.average_trunk_age),
+            ("AVG BRANCH AGE", self.average_branch_age),
+            ("SAME TRUNK AND BRANCH", self.same_trunk_and_branch),
+            ("TRUNK ROOT APPROVALS", self.trunk_root_approvals),
+            ("BRANCH ROOT APPROVALS", self.branch_root_approvals),
+        ]
+    }
+}
+
+/// The accumulator for statistics that tell trunks and branches apart.
+pub struct TrunkBranch<'a> {
+    /// Keep a reference to the graph to follow trunks and look up referred transactions.
+    graph: &'a Graph,
+
+    /// The unit of the timestamps.
+    unit: TimeUnit,
+
+    /// The trunk chain length calculation cache.
+    cache: Map<NonRootId, usize>,
+
+    /// The sum of the trunk chain lengths of all transactions.
+    sum_of_trunk_lengths: usize,
+
+    /// The longest trunk chain.
+    max_trunk_length: usize,
+
+    /// The ages of the transactions referred to by trunks.
+    trunk_ages: Ages,
+
+    /// The ages of the transactions referred to by branches.
+    branch_ages: Ages,
+
+    /// The number of transactions whose trunk and branch are the same transaction.
+    same_trunk_and_branch: usize,
+
+    /// The number of trunks referring to the Root.
+    trunk_root_approvals: usize,
+
+    /// The number of branches referring to the Root.
+    branch_root_approvals: usize,
+
+    /// The first error converting an age, reported with the result.
+    error: Option<PosOverflow<usize>>,
+}
+
+impl<'a> TrunkBranch<'a> {
+    /// The statistics in the time unit of the graph.
+    pub fn new(graph: &'a Graph) -> Self {
+        Self::with_unit(graph, TimeUnit::of(graph))
+    }
+
+    pub fn with_unit(graph: &'a Graph, unit: TimeUnit) -> Self {
+        Self {
+            graph,
+            unit,
+            cache: Map::with_capacity(graph.len()),
+            sum_of_trunk_lengths: 0,
+            max_trunk_length: 0,
+            trunk_ages: Ages::default(),
+            branch_ages: Ages::default(),
+            same_trunk_and_branch: 0,
+            trunk_root_approvals: 0,
+            branch_root_approvals: 0,
+            error: None,
+        }
+    }
+}
+
+impl<'a> Stat<'a> for TrunkBranch<'a> {
+    fn accumulate(&mut self, transaction: &Transaction) {
+        let length = self.graph.trunk_length(transaction.id(), &mut self.cache);
+        self.sum_of_trunk_lengths += length;
+        self.max_trunk_length = self.max_trunk_length.max(length);
+
+        // Like the same parents of anomalies, references to the Root do not count.
+        if let (Id::Transaction(left), Id::Transaction(right)) =
+            (transaction.left(), transaction.right())
+        {
+            if left == right {
+                self.same_trunk_and_branch += 1;
+            }
+        }
+
+        let timestamp = transaction.timestamp();
+        let sides = [
+            (
+                transaction.left(),
+                &mut self.trunk_ages,
+                &mut self.trunk_root_approvals,
+            ),
+            (
+                transaction.right(),
+                &mut self.branch_ages,
+                &mut self.branch_root_approvals,
+            ),
+        ];
+
+        for (reference, ages, root_approvals) in sides {
+            match reference {
+                Id::Transaction(parent) => {
+                    let parent = self.graph[parent].timestamp();
+                    if let Err(e) = ages.add(timestamp, parent) {
+                        self.error.get_or_insert(e);
+                    }
+                }
+                Id::Root => *root_approvals += 1,
+            }
+        }
+    }
+
+    fn result(&self, n_transactions: f64) -> Result<Box<dyn StatResult>, PosOverflow<usize>> {
+        if let Some(e) = self.error {
+            return Err(e);
+        }
+
+        Ok(Box::new(TrunkBranchResult {
+            unit: self.unit,
+            average_trunk_length: f64::value_from(self.sum_of_trunk_lengths)? / n_transactions,
+            max_trunk_length: f64::value_from(self.max_trunk_length)?,
+            average_trunk_age: self.trunk_ages.average()?,
+            average_branch_age: self.branch_ages.average()?,
+            same_trunk_and_branch: f64::value_from(self.same_trunk_and_branch)?,
+            trunk_root_approvals: f64::value_from(self.trunk_root_approvals)?,
+            branch_root_approvals: f64::value_from(self.branch_root_approvals)?,
+        }))
+    }
+}
+
+#[cfg(test)]
+mod trunk_tests {
+    use super::TrunkBranch;
+    use crate::graph::{test_graph, Graph};
+    use crate::stats::{self, Stat};
+    use std::collections::HashMap as Map;
+
+    fn trunk_branch(graph: &Graph) -> String {
+        let stats: Vec<Box<dyn Stat>> = vec![Box::new(TrunkBranch::new(graph))];
+        stats::accumulate(graph, stats).unwrap()[0].to_string()
+    }
+
+    #[test]
+    fn sides() {
+        let tangle = test_graph("5\n1 1 0\n1 2 0\n2 2 1\n3 6 3\n3 3 2\n");
+        assert_eq!(
+            trunk_branch(&tangle),
+            "> TRUNK CHAIN: AVG 1.60, MAX 2\n\
+             > AVG PARENT AGE: TRUNK 2.00 ticks, BRANCH 1.00 ticks\n\
+             > SAME TRUNK AND BRANCH: 2\n\
+             > ROOT APPROVALS: TRUNK 2, BRANCH 1"
+        );
+
+        // Branches referring to more recent transactions have negative ages.
+        let forward = test_graph("time-unit: s\n2\n3 3 10\n1 1 30\n");
+        assert_eq!(
+            trunk_branch(&forward),
+            "> TRUNK CHAIN: AVG 1.50, MAX 2\n\
+             > AVG PARENT AGE: TRUNK -20.00 s, BRANCH -20.00 s\n\
+             > SAME TRUNK AND BRANCH: 1\n\
+             > ROOT APPROVALS: TRUNK 1, BRANCH 1"
+        );
+    }
+
+    #[test]
+    fn trunk_length() {
+        let chain = test_graph("4\n1 1 0\n2 1 1\n3 1 2\n1 4 3\n");
+        let mut cache = Map::new();
+        let lengths: Vec<usize> = chain
+            .transactions()
+            .map(|t| chain.trunk_length(t.id(), &mut cache))
+            .collect();
+        assert_eq!(lengths, vec![1, 2, 3, 1]);
+    }
+}
@@ -0,0 +1,362 @@
+#![warn(clippy::all)]
+
+//! The width of a `Graph`: how many transactions there are at each depth and how many
+//! transactions are mutually unreachable, i.e. none of them refers to another, directly or
+//! not.
+
+use crate::graph::Graph;
+use crate::id::{Id, NonRootId};
+use crate::stats::{Stat, StatResult};
+use crate::time::Timestamp;
+use crate::transaction::Transaction;
+use conv::{errors::PosOverflow, ValueFrom};
+use derive_more::Display;
+use std::collections::HashMap as Map;
+use std::fmt::{self, Display};
+use std::iter;
+use std::num::NonZeroU64;
+
+/// Marks unmatched vertices and unreachable layers in the matching.
+const NONE: usize = usize::MAX;
+
+/// The width of the transactions with a timestamp in a window of time, displayed as a
+/// tab-separated row.
+#[derive(PartialEq, Debug, Display)]
+#[display(fmt = "{}\t{}", start, width)]
+pub struct WindowWidth {
+    /// The first timestamp of the window.
+    pub start: Timestamp,
+
+    /// The size of the largest set of mutually unreachable transactions in the window.
+    pub width: usize,
+}
+
+impl Graph {
+    /// The number of transactions at each depth, indexed by depth. The Root is the only
+    /// transaction at depth 0.
+    pub fn level_widths(&self) -> Vec<usize> {
+        let mut widths = vec![1];
+        let mut cache = Map::with_capacity(self.len());
+
+        for t in self.transactions() {
+            let depth = self.depth(t.id(), &mut cache);
+            if widths.len() <= depth {
+                widths.resize(depth + 1, 0);
+            }
+            widths[depth] += 1;
+        }
+
+        widths
+    }
+
+    /// The size of the largest set of mutually unreachable transactions. By Dilworth's
+    /// theorem, it is the number of transactions minus the size of a maximum matching
+    /// between the transactions and the transactions they reach. The reachability relation
+    /// takes quadratic memory, so this is only meant for moderate graphs. Assumes the
+    /// graph is acyclic.
+    pub fn max_antichain(&self) -> usize {
+        let ids: Vec<NonRootId> = self.transactions().map(Transaction::id).collect();
+        self.antichain(&ids)
+    }
+
+    /// Split the timestamps into windows of `size` like `Graph::windows`, and compute the
+    /// size of the largest set of mutually unreachable transactions issued within each
+    /// window. Transactions can reach each other through transactions of other windows.
+    pub fn window_widths(&self, size: NonZeroU64) -> Vec<WindowWidth> {
+        let bucket = |timestamp: Timestamp| timestamp / size.get();
+
+        let mut members: Map<Timestamp, Vec<NonRootId>> = Map::new();
+        for t in self.transactions() {
+            members
+                .entry(bucket(t.timestamp()))
+                .or_default()
+                .push(t.id());
+        }
+
+        let first = members.keys().min().copied();
+        let last = members.keys().max().copied();
+        let (first, last) = match (first, last) {
+            (Some(first), Some(last)) => (first, last),
+            _ => return Vec::new(),
+        };
+
+        (first..=last)
+            .map(|bucket| WindowWidth {
+                start: bucket * size.get(),
+                width: members.get(&bucket).map_or(0, |ids| self.antichain(ids)),
+            })
+            .collect()
+    }
+
+    /// The size of the largest set of mutually unreachable transactions among `ids`.
+    fn antichain(&self, ids: &[NonRootId]) -> usize {
+        let reach = self.reachability(ids);
+        ids.len() - Matching::new(&reach).maximum()
+    }
+
+    /// For each of `ids`, the bitset of the other `ids` it reaches, indexed like `ids`.
+    fn reachability(&self, ids: &[NonRootId]) -> Vec<Vec<u64>> {
+        let index: Map<NonRootId, usize> = ids.iter().enumerate().map(|(i, &id)| (id, i)).collect();
+        let n_words = ids.len().div_ceil(64);
+
+        // The bitsets of every transaction reached from `ids`, computed after the bitsets
+        // of the transactions they refer to.
+        let mut reached: Map<NonRootId, Vec<u64>> = Map::new();
+        let mut stack: Vec<(NonRootId, bool)> = ids.iter().map(|&id| (id, false)).collect();
+
+        while let Some((id, expanded)) = stack.pop() {
+            if reached.contains_key(&id) {
+                continue;
+            }
+
+            let t = &self[id];
+            let references = [t.left(), t.right()];
+            let parents = references.iter().filter_map(|&id| match id {
+                Id::Transaction(id) => Some(id),
+                Id::Root => None,
+            });
+
+            if expanded {
+                let mut bits = vec![0; n_words];
+                for parent in parents {
+                    if let Some(&i) = index.get(&parent) {
+                        bits[i / 64] |= 1 << (i % 64);
+                    }
+                    for (word, parent_word) in bits.iter_mut().zip(&reached[&parent]) {
+                        *word |= parent_word;
+                    }
+                }
+                reached.insert(id, bits);
+            } else {
+                stack.push((id, true));
+                stack.extend(
+                    parents
+                        .filter(|p| !reached.contains_key(p))
+                        .map(|p| (p, false)),
+                );
+            }
+        }
+
+        ids.iter()
+            .map(|id| reached.remove(id).expect("The IDs are reached"))
+            .collect()
+    }
+}
+
+/// A maximum matching in a bipartite graph between two copies of the same vertices,
+/// found with the Hopcroft-Karp algorithm.
+struct Matching<'a> {
+    /// The vertices adjacent to each vertex, as bitsets.
+    adjacent: &'a [Vec<u64>],
+
+    /// The vertex of the right copy matched to each vertex of the left copy.
+    right_of: Vec<usize>,
+
+    /// The vertex of the left copy matched to each vertex of the right copy.
+    left_of: Vec<usize>,
+
+    /// The layer of each vertex of the left copy in the current phase.
+    layers: Vec<usize>,
+}
+
+impl<'a> Matching<'a> {
+    fn new(adjacent: &'a [Vec<u64>]) -> Self {
+        let n = adjacent.len();
+        Self {
+            adjacent,
+            right_of: vec![NONE; n],
+            left_of: vec![NONE; n],
+            layers: vec![NONE; n],
+        }
+    }
+
+    /// The vertices adjacent to a vertex of the left copy.
+    fn neighbours(&self, u: usize) -> impl Iterator<Item = usize> + 'a {
+        let adjacent: &'a [Vec<u64>] = self.adjacent;
+        adjacent[u].iter().enumerate().flat_map(|(i, &word)| {
+            // Pop the lowest set bit until none are left.
+            let mut word = word;
+            iter::from_fn(move || {
+                let b = word.trailing_zeros();
+                word &= word.wrapping_sub(1);
+                (b < 64).then(|| i * 64 + b as usize)
+            })
+        })
+    }
+
+    /// The size of a maximum matching.
+    fn maximum(mut self) -> usize {
+        let mut size = 0;
+
+        while self.layer() {
+            for u in 0..self.adjacent.len() {
+                if self.right_of[u] == NONE && self.augment(u) {
+                    size += 1;
+                }
+            }
+        }
+
+        size
+    }
+
+    /// Split the left copy in layers by breadth-first search from its unmatched vertices,
+    /// returning whether an augmenting path exists.
+    fn layer(&mut self) -> bool {
+        let mut queue = Vec::new();
+        for u in 0..self.adjacent.len() {
+            if self.right_of[u] == NONE {
+                self.layers[u] = 0;
+                queue.push(u);
+            } else {
+                self.layers[u] = NONE;
+            }
+        }
+
+        let mut found = false;
+        let mut i = 0;
+        while i < queue.len() {
+            let u = queue[i];
+            i += 1;
+
+            for v in self.neighbours(u) {
+                match self.left_of[v] {
+                    NONE => found = true,
+                    w if self.layers[w] == NONE => {
+                        self.layers[w] = self.layers[u] + 1;
+                        queue.push(w);
+                    }
+                    _ => (),
+                }
+            }
+        }
+
+        found
+    }
+
+    /// Find an augmenting path from `u` along the layers and flip it.
+    fn augment(&mut self, u: usize) -> bool {
+        for v in self.neighbours(u) {
+            let w = self.left_of[v];
+            let next = w == NONE || (self.layers[w] == self.layers[u] + 1 && self.augment(w));
+
+            if next {
+                self.right_of[u] = v;
+                self.left_of[v] = u;
+                return true;
+            }
+        }
+
+        // There is no augmenting path through `u` in this phase.
+        self.layers[u] = NONE;
+        false
+    }
+}
+
+/// The result of width statistics.
+pub struct WidthResult {
+    pub(crate) max_txs_per_depth: f64,
+    pub(crate) max_antichain: f64,
+}
+
+impl Display for WidthResult {
+    fn fmt(&self, f: &mut fmt::Formatter) -> fmt::Result {
+        writeln!(f, "> MAX TXS PER DEPTH: {}", self.max_txs_per_depth)?;
+        write!(f, "> MAX ANTICHAIN: {}", self.max_antichain)
+    }
+}
+
+impl StatResult for WidthResult {
+    fn values(&self) -> Vec<(&'static str, f64)> {
+        vec![
+            ("MAX TXS PER DEPTH", self.max_txs_per_depth),
+            ("MAX ANTICHAIN", self.max_antichain),
+        ]
+    }
+}
+
+/// The accumulator for statistics related to the width of the graph.
+pub struct Width<'a> {
+    /// Keep a reference to the graph so that we can call depth() and max_antichain().
+    graph: &'a Graph,
+
+    /// The depth calculation cache to avoid recomputing depths that have already been
+    /// computed.
+    cache: Map<NonRootId, usize>,
+
+    /// The number of transactions at each depth.
+    txs_per_depth: Map<usize, usize>,
+}
+
+impl<'a> Width<'a> {
+    pub fn new(graph: &'a Graph) -> Self {
+        Self {
+            graph,
+            cache: Map::with_capacity(graph.len()),
+            txs_per_depth: Map::new(),
+        }
+    }
+}
+
+impl<'a> Stat<'a> for Width<'a> {
+    fn accumulate(&mut self, transaction: &Transaction) {
+        let depth = self.graph.depth(transaction.id(), &mut self.cache);
+        *self.txs_per_depth.entry(depth).or_insert(0) += 1;
+    }
+
+    fn result(&self, _n_transactions: f64) -> Result<Box<dyn StatResult>, PosOverflow<usize>> {
+        let max_txs_per_depth = self.txs_per_depth.values().copied().max().unwrap_or(0);
+        Ok(Box::new(WidthResult {
+            max_txs_per_depth: f64::value_from(max_txs_per_depth)?,
+            max_antichain: f64::value_from(self.graph.max_antichain())?,
+        }))
+    }
+}
+
+#[cfg(test)]
+mod width_tests {
+    use super::Width;
+    use crate::graph::test_graph;
+    use crate::stats::{self, Stat};
+    use std::num::NonZeroU64;
+
+    #[test]
+    fn levels_and_antichains() {
+        let tangle = test_graph("5\n1 1 10\n1 1 10\n2 3 20\n2 2 20\n4 5 30\n");
+        assert_eq!(tangle.level_widths(), vec![1, 2, 2, 1]);
+        assert_eq!(tangle.max_antichain(), 2);
+
+        let stats: Vec<Box<dyn Stat>> = vec![Box::new(Width::new(&tangle))];
+        let results = stats::accumulate(&tangle, stats).unwrap();
+        assert_eq!(
+            results[0].to_string(),
+            "> MAX TXS PER DEPTH: 2\n> MAX ANTICHAIN: 2"
+        );
+
+        // Transactions that only refer to the Root are mutually unreachable.
+        assert_eq!(
+            test_graph("4\n1 1 0\n1 1 0\n1 1 0\n1 1 0\n").max_antichain(),
+            4
+        );
+
+        // A chain has a single transaction at each depth.
+        let chain = test_graph("4\n1 1 0\n2 2 1\n3 3 2\n4 4 3\n");
+        assert_eq!(chain.level_widths(), vec![1, 1, 1, 1, 1]);
+        assert_eq!(chain.max_antichain(), 1);
+
+        assert_eq!(test_graph("0\n").level_widths(), vec![1]);
+        assert_eq!(test_graph("0\n").max_antichain(), 0);
+    }
+
+    #[test]
+    fn window_widths() {
+        let graph = test_graph("5\n1 1 10\n1 1 10\n2 3 20\n2 2 20\n4 5 30\n");
+        let widths = |size| -> Vec<String> {
+            let widths = graph.window_widths(NonZeroU64::new(size).unwrap());
+            widths.iter().map(ToString::to_string).collect()
+        };
+
+        assert_eq!(widths(10), vec!["10\t2", "20\t2", "30\t1"]);
+        assert_eq!(widths(5), vec!["10\t2", "15\t0", "20\t2", "25\t0", "30\t1"]);
+        assert_eq!(widths(100), vec!["0\t2"]);
+    }
+}
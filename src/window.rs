@@ -0,0 +1,177 @@
+#![warn(clippy::all)]
+
+//! Statistics over windows of time, giving a series that shows how the ledger evolves
+//! instead of a single value for the whole trace.
+
+use crate::graph::Graph;
+use crate::id::Id;
+use crate::time::Timestamp;
+use conv::{errors::PosOverflow, ValueFrom};
+use derive_more::Display;
+use std::collections::HashMap as Map;
+use std::convert::TryFrom;
+use std::num::NonZeroU64;
+
+/// The statistics of the transactions with a timestamp in a window of time, displayed as
+/// a tab-separated row.
+#[derive(PartialEq, Debug, Display)]
+#[display(
+    fmt = "{}\t{}\t{:.2}\t{:.2}\t{}",
+    start,
+    arrivals,
+    average_depth,
+    average_approvals,
+    tips
+)]
+pub struct Window {
+    /// The first timestamp of the window.
+    pub start: Timestamp,
+
+    /// The number of transactions with a timestamp in the window.
+    pub arrivals: usize,
+
+    /// The average depth of these transactions, NaN if there are none.
+    pub average_depth: f64,
+
+    /// The average number of references to these transactions by the end of the window,
+    /// NaN if there are none.
+    pub average_approvals: f64,
+
+    /// The number of transactions that nothing refers to by the end of the window.
+    pub tips: usize,
+}
+
+impl Graph {
+    /// Split the timestamps into windows of `size`, starting at multiples of `size`, and
+    /// compute the statistics of each window from the first transaction to the last one,
+    /// including the windows without transactions. References are only counted from the
+    /// window of the timestamp of the transaction they belong to.
+    pub fn windows(&self, size: NonZeroU64) -> Result<Vec<Window>, PosOverflow<usize>> {
+        let bucket = |timestamp: Timestamp| timestamp / size.get();
+
+        let first = match self.transactions().map(|t| bucket(t.timestamp())).min() {
+            Some(first) => first,
+            None => return Ok(Vec::new()),
+        };
+        let last = self
+            .transactions()
+            .map(|t| bucket(t.timestamp()))
+            .max()
+            .unwrap_or(first);
+        let n_windows = usize::try_from(last - first + 1).expect("The windows fit in memory");
+        let index = |timestamp: Timestamp| {
+            usize::try_from(bucket(timestamp) - first).expect("Windows are indexed by usize")
+        };
+        let window = |id: Id| match id {
+            Id::Transaction(id) => Some(index(self[id].timestamp())),
+            Id::Root => None,
+        };
+
+        let mut arrivals = vec![0; n_windows];
+        let mut sums_of_depths = vec![0; n_windows];
+        let mut approvals = vec![0; n_windows];
+
+        // The window of the first reference to each transaction.
+        let mut first_approvals = Map::with_capacity(self.len());
+        let mut cache = Map::with_capacity(self.len());
+
+        for t in self.transactions() {
+            let own = index(t.timestamp());
+            arrivals[own] += 1;
+            sums_of_depths[own] += self.depth(t.id(), &mut cache);
+
+            for &reference in &[t.left(), t.right()] {
+                if let Some(approved) = window(reference) {
+                    if own <= approved {
+                        approvals[approved] += 1;
+                    }
+
+                    let first_approval = first_approvals.entry(reference).or_insert(own);
+                    *first_approval = own.min(*first_approval);
+                }
+            }
+        }
+
+        // The transactions are tips from their own window until the window before the first
+        // reference to them.
+        let mut new_tips = vec![0; n_windows];
+        let mut approved_tips = vec![0; n_windows];
+        for t in self.transactions() {
+            let own = index(t.timestamp());
+            match first_approvals.get(&Id::from(t.id())) {
+                Some(&approved) if approved <= own => (),
+                Some(&approved) => {
+                    new_tips[own] += 1;
+                    approved_tips[approved] += 1;
+                }
+                None => new_tips[own] += 1,
+            }
+        }
+
+        let mut windows = Vec::with_capacity(n_windows);
+        let mut tips = 0;
+
+        for (i, bucket) in (first..=last).enumerate() {
+            tips = tips + new_tips[i] - approved_tips[i];
+
+            let n_arrivals = f64::value_from(arrivals[i])?;
+            windows.push(Window {
+                start: bucket * size.get(),
+                arrivals: arrivals[i],
+                average_depth: f64::value_from(sums_of_depths[i])? / n_arrivals,
+                average_approvals: f64::value_from(approvals[i])? / n_arrivals,
+                tips,
+            });
+        }
+
+        Ok(windows)
+    }
+}
+
+#[cfg(test)]
+mod window_tests {
+    use crate::graph::Graph;
+    use std::convert::TryFrom;
+    use std::io::BufReader;
+    use std::num::NonZeroU64;
+
+    fn windows(input: &str, size: u64) -> Vec<String> {
+        let graph = Graph::try_from(BufReader::new(input.as_bytes())).unwrap();
+        let windows = graph.windows(NonZeroU64::new(size).unwrap()).unwrap();
+        windows.iter().map(ToString::to_string).collect()
+    }
+
+    #[test]
+    fn windows_series() {
+        let input = "5\n1 1 100\n1 1 105\n2 3 125\n4 1 126\n4 5 170\n";
+        assert_eq!(
+            windows(input, 20),
+            vec![
+                "100\t2\t1.00\t0.00\t2",
+                "120\t2\t1.50\t0.50\t1",
+                "140\t0\tNaN\tNaN\t1",
+                "160\t1\t2.00\t0.00\t1",
+            ]
+        );
+
+        // A single window covers the whole graph.
+        assert_eq!(windows(input, 1000), vec!["0\t5\t1.40\t1.00\t1"]);
+    }
+
+    #[test]
+    fn forward_references() {
+        // Transaction 2 refers to transaction 3 that comes after it, with a later timestamp.
+        let input = "2\n3 3 130\n1 1 100\n";
+        assert_eq!(
+            windows(input, 10),
+            vec![
+                "100\t1\t1.00\t0.00\t1",
+                "110\t0\tNaN\tNaN\t1",
+                "120\t0\tNaN\tNaN\t1",
+                "130\t1\t2.00\t0.00\t1",
+            ]
+        );
+
+        assert_eq!(windows("0\n", 10), Vec::<String>::new());
+    }
+}
@@ -1,10 +1,27 @@
 use conv::ValueFrom;
-use graphstats::graph::Graph;
+use graphstats::arrivals::Arrivals;
+use graphstats::graph::{DepthPath, Graph};
+use graphstats::incremental::{self, ObservedGraph};
 use graphstats::stats::{self, Stat};
+use std::collections::HashSet;
 use std::convert::TryFrom;
 use std::fmt::Write;
 use std::fs::{self, File};
 use std::io::BufReader;
+use std::path::PathBuf;
+
+/// The graphs of the input files in `testdata`, along with their paths.
+fn test_graphs() -> impl Iterator<Item = (PathBuf, Graph)> {
+    fs::read_dir("testdata")
+        .unwrap()
+        .map(|entry| entry.unwrap().path())
+        .filter(|path| path.extension().unwrap() == "in")
+        .map(|path| {
+            let input_file = File::open(&path).unwrap();
+            let graph = Graph::try_from(BufReader::new(input_file)).unwrap();
+            (path, graph)
+        })
+}
 
 #[test]
 fn test_files() {
@@ -24,7 +41,7 @@ fn test_files() {
             let mut stats: Vec<Box<dyn Stat>> = vec![
                 Box::new(stats::Depths::new(&graph)),
                 Box::new(stats::InReferences::new(&graph)),
-                Box::new(stats::TimeUnits::default()),
+                Box::new(stats::Throughput::new(&graph)),
                 Box::new(stats::Timestamps::new(&graph)),
             ];
 
@@ -53,3 +70,119 @@ fn test_files() {
         }
     }
 }
+
+#[test]
+fn test_files_parallel() {
+    for (path, expected) in test_graphs() {
+        let input = fs::read(&path).unwrap();
+
+        for jobs in &[1, 3, 8] {
+            let graph = Graph::try_from_parallel(&input, *jobs).unwrap();
+            assert_eq!(graph, expected);
+
+            let graph = Graph::try_from_file(File::open(&path).unwrap(), *jobs).unwrap();
+            assert_eq!(graph, expected);
+        }
+    }
+}
+
+#[test]
+fn test_files_incremental() {
+    for (mut path, graph) in test_graphs() {
+        // Subscribing replays the transactions already in the graph.
+        let mut observed = ObservedGraph::new(graph);
+        observed.subscribe(Box::new(incremental::Depths::default()));
+        observed.subscribe(Box::new(incremental::InReferences::default()));
+        observed.subscribe(Box::new(stats::Throughput::default()));
+        observed.subscribe(Box::new(stats::Timestamps::default()));
+
+        let mut actual_output = String::new();
+        for result in observed.results().unwrap() {
+            writeln!(&mut actual_output, "{}", result).unwrap();
+        }
+
+        path.set_extension("out");
+        let expected_output = fs::read_to_string(&path).unwrap();
+        assert_eq!(actual_output, expected_output);
+    }
+}
+
+#[test]
+fn test_files_canonical() {
+    let mut hashes = HashSet::new();
+
+    for (path, graph) in test_graphs() {
+        // Relabeling a canonical graph keeps it as is.
+        let canonical = graph.canonical().unwrap();
+        assert_eq!(canonical.canonical().unwrap(), canonical);
+
+        // No two fixtures are the same graph.
+        assert!(hashes.insert(graph.canonical_hash().unwrap()), "{:?}", path);
+    }
+}
+
+#[test]
+fn test_files_canonical_renumbered() {
+    for (path, graph) in test_graphs() {
+        // Number the transactions the other way round, which reorders the tied ones.
+        let n = graph.len() + 1;
+        let renumber = |id| match usize::from(id) {
+            1 => 1,
+            id => n + 2 - id,
+        };
+
+        let mut input = format!("{}\n", graph.len());
+        for t in graph.transactions().collect::<Vec<_>>().into_iter().rev() {
+            let (left, right) = (renumber(t.left()), renumber(t.right()));
+            writeln!(input, "{} {} {}", left, right, t.timestamp()).unwrap();
+        }
+        let renumbered = Graph::try_from(BufReader::new(input.as_bytes())).unwrap();
+
+        assert_eq!(renumbered.canonical(), graph.canonical(), "{:?}", path);
+        assert_eq!(
+            renumbered.canonical_hash(),
+            graph.canonical_hash(),
+            "{:?}",
+            path
+        );
+    }
+}
+
+#[test]
+fn test_files_arrivals() {
+    for (path, graph) in test_graphs() {
+        let stats: Vec<Box<dyn Stat>> = vec![Box::new(Arrivals::new(&graph))];
+        let batch = stats::accumulate(&graph, stats).unwrap()[0].to_string();
+
+        let mut observed = ObservedGraph::new(graph);
+        observed.subscribe(Box::new(Arrivals::default()));
+        let incremental = observed.results().unwrap()[0].to_string();
+
+        assert_eq!(incremental, batch, "{:?}", path);
+    }
+}
+
+#[test]
+fn test_files_heights() {
+    for (_, graph) in test_graphs() {
+        let stats: Vec<Box<dyn Stat>> = vec![
+            Box::new(stats::Heights::new(&graph)),
+            Box::new(stats::Depths::with_path(&graph, DepthPath::Longest)),
+            Box::new(stats::Depths::new(&graph)),
+        ];
+        let results = stats::accumulate(&graph, stats).unwrap();
+        let value = |i: usize, name| {
+            let values = results[i].values();
+            values.iter().find(|(n, _)| *n == name).unwrap().1
+        };
+
+        // The longest paths are the heights, and they are never shorter than the shortest
+        // ones, by the average gap between them.
+        let average_height = value(0, "AVG HEIGHT (INCL. ROOT)");
+        let average_gap = value(0, "AVG HEIGHT - DEPTH (INCL. ROOT)");
+        assert_eq!(average_height, value(1, "AVG DAG DEPTH"));
+        assert!(average_gap >= 0.0);
+        let gap = average_height - value(2, "AVG DAG DEPTH");
+        assert!((average_gap - gap).abs() < 1e-9 * average_height.max(1.0));
+    }
+}